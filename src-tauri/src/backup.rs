@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use zip::write::SimpleFileOptions;
+
+use crate::config::overrides::{self, Overrides};
+use crate::config::{self, GeneratedConfig};
+use crate::datasets;
+use crate::http::HttpClient;
+use crate::paths;
+use crate::profiles::{self, Profile};
+use crate::rules::schedule::UpdateLock;
+use crate::rules::{archive, local, policy};
+use crate::settings::{self, Settings};
+use crate::threshold;
+
+// Bumped when the layout of the export changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+// Files and directories, relative to the data directory, that make up the
+// configuration. Logs, downloaded rules and the EveBox install are left out.
+const FILES: &[&str] = &[
+    SETTINGS_FILE,
+    "disable.conf",
+    "enable.conf",
+    "drop.conf",
+    "modify.conf",
+    "threshold.conf",
+    "config/overrides.json",
+    "config/suricata.yaml",
+];
+const DIRS: &[&str] = &["local-rules", "profiles", "datasets", "iprep"];
+
+// Files that change the rules on the next update
+const POLICY_FILES: &[&str] = &["disable.conf", "enable.conf", "drop.conf", "modify.conf"];
+
+// Built from the other files. Exported for reference but regenerated on
+// import, as it contains paths of the machine it was generated on.
+const GENERATED: &[&str] = &["config/suricata.yaml"];
+
+// Limits on what an import may unpack to
+const MAX_ENTRIES: usize = 10_000;
+const MAX_TOTAL_SIZE: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+// Written to the archive as manifest.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub app_version: String,
+    pub created_at: String,
+    // False when proxy passwords, auth headers and secret codes were blanked
+    pub secrets_included: bool,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigExport {
+    pub path: String,
+    pub files: usize,
+    pub secrets_included: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Added,
+    Modified,
+    Removed,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedFile {
+    pub path: String,
+    pub change: FileChange,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigImport {
+    // Nothing was written, files show what an import would change
+    pub dry_run: bool,
+    pub app_version: String,
+    pub created_at: String,
+    pub files: Vec<ImportedFile>,
+    // The regenerated suricata.yaml, None on a dry run
+    pub config: Option<GeneratedConfig>,
+    // Rule sources, policies or local rules changed, rules need updating to
+    // match
+    pub rules_update_needed: bool,
+    pub message: String,
+}
+
+fn local_path(data_dir: &Path, path: &str) -> PathBuf {
+    path.split('/')
+        .fold(data_dir.to_path_buf(), |path, part| path.join(part))
+}
+
+fn collect_dir(data_dir: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_dir(data_dir, &path, files);
+        } else if file_type.is_file() {
+            if let Some(relative) = path
+                .strip_prefix(data_dir)
+                .ok()
+                .and_then(|relative| archive::normalize_path(relative).ok())
+            {
+                files.push(relative);
+            }
+        }
+    }
+}
+
+// Paths of the configuration files on disk, relative to the data directory
+fn collect_files(data_dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = FILES
+        .iter()
+        .filter(|path| local_path(data_dir, path).is_file())
+        .map(|path| path.to_string())
+        .collect();
+    for dir in DIRS {
+        collect_dir(data_dir, &data_dir.join(dir), &mut files);
+    }
+    files.sort();
+    files
+}
+
+// Contents of files saved before a change writes them, so a change that
+// fails partway can put every file back as it was
+#[derive(Default)]
+pub struct FileSnapshot {
+    // None for files that didn't exist
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl FileSnapshot {
+    pub fn add(&mut self, path: PathBuf) -> Result<(), String> {
+        let data = match std::fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        self.files.push((path, data));
+        Ok(())
+    }
+
+    // Put the saved files back, removing the ones that didn't exist. Every
+    // file is attempted, the first failure is returned.
+    pub fn restore(self) -> Result<(), String> {
+        let mut result = Ok(());
+        for (path, data) in self.files.into_iter().rev() {
+            let restored = match data {
+                Some(data) => path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::write(&path, data)),
+                None => match std::fs::remove_file(&path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    other => other,
+                },
+            };
+            if let Err(e) = restored {
+                if result.is_ok() {
+                    result = Err(format!("Failed to restore {}: {}", path.display(), e));
+                }
+            }
+        }
+        result
+    }
+}
+
+fn strip_secrets(settings: &mut Settings) {
+    settings.proxy.password.clear();
+    for source in &mut settings.rule_sources {
+        source.auth_header.clear();
+        source.secret_code.clear();
+    }
+}
+
+// Secrets left blank in imported settings keep their current values
+fn keep_secrets(settings: &mut Settings, current: &Settings) {
+    if settings.proxy.password.is_empty()
+        && settings.proxy.url == current.proxy.url
+        && settings.proxy.username == current.proxy.username
+    {
+        settings.proxy.password = current.proxy.password.clone();
+    }
+    for source in &mut settings.rule_sources {
+        let Some(existing) = current
+            .rule_sources
+            .iter()
+            .find(|existing| existing.name == source.name)
+        else {
+            continue;
+        };
+        if source.auth_header.is_empty() {
+            source.auth_header = existing.auth_header.clone();
+        }
+        if source.secret_code.is_empty() {
+            source.secret_code = existing.secret_code.clone();
+        }
+    }
+}
+
+fn write_archive(path: &str, include_secrets: bool) -> Result<Manifest, String> {
+    let data_dir = paths::data_dir()?;
+    let file =
+        std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut manifest = Manifest {
+        format: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        secrets_included: include_secrets,
+        files: Vec::new(),
+    };
+
+    // Settings are always exported, the defaults when none have been saved
+    let mut settings = settings::load()?;
+    if !include_secrets {
+        strip_secrets(&mut settings);
+    }
+    let settings = serde_json::to_vec_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let mut files = vec![(SETTINGS_FILE.to_string(), settings)];
+    for relative in collect_files(&data_dir) {
+        if relative == SETTINGS_FILE {
+            continue;
+        }
+        let data = std::fs::read(local_path(&data_dir, &relative))
+            .map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        files.push((relative, data));
+    }
+
+    for (relative, data) in files {
+        zip.start_file(relative.as_str(), options)
+            .and_then(|_| zip.write_all(&data).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to the archive: {}", relative, e))?;
+        manifest.files.push(ManifestFile {
+            sha256: hex::encode(Sha256::digest(&data)),
+            size: data.len() as u64,
+            path: relative,
+        });
+    }
+
+    let content = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(MANIFEST_FILE, options)
+        .and_then(|_| zip.write_all(&content).map_err(Into::into))
+        .map_err(|e| format!("Failed to add the manifest to the archive: {}", e))?;
+    zip.finish()
+        .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(manifest)
+}
+
+// Read every file of an exported archive into memory, by normalized path
+fn read_archive(path: &str) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(format!("Archive has more than {} entries", MAX_ENTRIES));
+    }
+
+    let mut entries = BTreeMap::new();
+    let mut total = 0;
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if !file.is_file() || file.is_symlink() {
+            continue;
+        }
+        let name = archive::normalize_path(Path::new(file.name()))?;
+        // Never trust the declared sizes
+        let mut data = Vec::new();
+        file.take(MAX_TOTAL_SIZE - total + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {} from archive: {}", name, e))?;
+        total += data.len() as u64;
+        if total > MAX_TOTAL_SIZE {
+            return Err(format!(
+                "Archive unpacks to more than {} MB",
+                MAX_TOTAL_SIZE / 1024 / 1024
+            ));
+        }
+        if entries.insert(name.clone(), data).is_some() {
+            return Err(format!("Archive contains {} more than once", name));
+        }
+    }
+    Ok(entries)
+}
+
+// Check an imported file is one the app manages and has valid content.
+// Settings are checked separately.
+fn validate_file(path: &str, data: &[u8]) -> Result<(), String> {
+    let text = std::str::from_utf8(data).map_err(|_| format!("{} is not valid text", path))?;
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.as_slice() {
+        [name @ ("disable.conf" | "enable.conf" | "drop.conf" | "modify.conf")] => {
+            policy::validate_file(name, text)
+        }
+        ["threshold.conf"] => threshold::validate_content(text),
+        ["config", "overrides.json"] => {
+            let overrides: Overrides = serde_json::from_str(text)
+                .map_err(|e| format!("Failed to parse config overrides: {}", e))?;
+            overrides::validate(&overrides)
+        }
+        ["config", "suricata.yaml"] => Ok(()),
+        ["local-rules", name] => local::validate_file_name(name),
+        ["profiles", "active"] => profiles::validate_name(text.trim()),
+        ["profiles", name, "profile.json"] => {
+            profiles::validate_name(name)?;
+            let profile: Profile = serde_json::from_str(text)
+                .map_err(|e| format!("Failed to parse profile {}: {}", name, e))?;
+            if profile.name != *name {
+                return Err(format!(
+                    "Profile {} is stored under the name {}",
+                    profile.name, name
+                ));
+            }
+            overrides::validate(&profile.config_overrides)
+                .map_err(|e| format!("Profile {}: {}", name, e))
+        }
+        ["profiles", name, "threshold.conf"] => {
+            profiles::validate_name(name)?;
+            threshold::validate_content(text).map_err(|e| format!("Profile {}: {}", name, e))
+        }
+        ["datasets", "datasets.json"] => datasets::parse_manifest(text).map(|_| ()),
+        // Checked by validate_datasets, with the manifest and categories
+        ["datasets", name] if name.ends_with(".lst") => Ok(()),
+        ["iprep", "categories.txt" | "reputation.list"] => Ok(()),
+        _ => Err(format!("Unexpected file {} in archive", path)),
+    }
+}
+
+// Check dataset lists against the types in the exported manifest, and
+// reputation entries against the exported categories
+fn validate_datasets(entries: &BTreeMap<String, Vec<u8>>) -> Result<(), String> {
+    let text = |path: &str| entries.get(path).map(|data| String::from_utf8_lossy(data));
+
+    let manifest = match text("datasets/datasets.json") {
+        Some(content) => datasets::parse_manifest(&content)?,
+        None => Vec::new(),
+    };
+    for (path, data) in entries {
+        let Some(name) = path
+            .strip_prefix("datasets/")
+            .and_then(|name| name.strip_suffix(".lst"))
+        else {
+            continue;
+        };
+        let dataset = manifest
+            .iter()
+            .find(|dataset| dataset.name == name)
+            .ok_or_else(|| format!("{} is not listed in datasets.json", path))?;
+        datasets::validate_list(dataset, &String::from_utf8_lossy(data))?;
+    }
+
+    let categories = match text("iprep/categories.txt") {
+        Some(content) => datasets::parse_iprep_categories(&content)?,
+        None => Vec::new(),
+    };
+    match text("iprep/reputation.list") {
+        Some(content) => datasets::validate_reputation(&content, &categories),
+        None => Ok(()),
+    }
+}
+
+// Export the configuration to a zip archive. Secrets are blanked unless
+// include_secrets is set.
+#[tauri::command]
+pub fn export_config(path: String, include_secrets: Option<bool>) -> Result<ConfigExport, String> {
+    let include_secrets = include_secrets.unwrap_or(false);
+    let manifest = match write_archive(&path, include_secrets) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+    Ok(ConfigExport {
+        message: format!("Exported {} files to {}", manifest.files.len(), path),
+        path,
+        files: manifest.files.len(),
+        secrets_included: include_secrets,
+    })
+}
+
+// Import an exported configuration. Everything is validated before anything
+// is written; with dry_run only the changes that would be made are returned.
+#[tauri::command]
+pub fn import_config(
+    app: AppHandle,
+    http_client: State<'_, HttpClient>,
+    path: String,
+    dry_run: Option<bool>,
+) -> Result<ConfigImport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let mut entries = read_archive(&path)?;
+
+    let manifest = entries
+        .remove(MANIFEST_FILE)
+        .ok_or_else(|| format!("{} is not a Meerkat Desktop configuration export", path))?;
+    let manifest: Manifest = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("Failed to parse the export manifest: {}", e))?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!(
+            "The export was made by a newer version of Meerkat Desktop ({}), update to import it",
+            manifest.app_version
+        ));
+    }
+
+    // Every file must be listed in the manifest with a matching checksum
+    for file in &manifest.files {
+        let data = entries.get(&file.path).ok_or_else(|| {
+            format!(
+                "{} is listed in the manifest but missing from the archive",
+                file.path
+            )
+        })?;
+        if hex::encode(Sha256::digest(data)) != file.sha256 {
+            return Err(format!(
+                "Checksum mismatch for {}, the archive is corrupt",
+                file.path
+            ));
+        }
+    }
+    for (file, data) in &entries {
+        if !manifest.files.iter().any(|listed| listed.path == *file) {
+            return Err(format!("{} is not listed in the manifest", file));
+        }
+        if file != SETTINGS_FILE {
+            validate_file(file, data)?;
+        }
+    }
+    validate_datasets(&entries)?;
+
+    let current = settings::load()?;
+    let settings = entries
+        .get(SETTINGS_FILE)
+        .ok_or_else(|| "The export has no settings".to_string())?;
+    let mut settings: Settings =
+        serde_json::from_slice(settings).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    keep_secrets(&mut settings, &current);
+    settings::validate(&settings)?;
+    let client = crate::http::build_client(&settings)?;
+    // Make sure the editor overrides still apply to the imported settings
+    let imported_overrides: Overrides = match entries.get("config/overrides.json") {
+        Some(data) => serde_json::from_slice(data)
+            .map_err(|e| format!("Failed to parse config overrides: {}", e))?,
+        None => Overrides::new(),
+    };
+    config::build(&settings.suricata_config, &imported_overrides)?;
+    entries.insert(
+        SETTINGS_FILE.to_string(),
+        serde_json::to_vec_pretty(&settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?,
+    );
+    for generated in GENERATED {
+        entries.remove(*generated);
+    }
+
+    let data_dir = paths::data_dir()?;
+    let mut files: Vec<ImportedFile> = entries
+        .iter()
+        .map(|(file, data)| {
+            let change = match std::fs::read(local_path(&data_dir, file)) {
+                Ok(existing) if existing == *data => FileChange::Unchanged,
+                Ok(_) => FileChange::Modified,
+                Err(_) => FileChange::Added,
+            };
+            ImportedFile {
+                path: file.clone(),
+                change,
+            }
+        })
+        .collect();
+    // Configuration files not in the export are removed
+    files.extend(
+        collect_files(&data_dir)
+            .into_iter()
+            .filter(|file| !entries.contains_key(file) && !GENERATED.contains(&file.as_str()))
+            .map(|file| ImportedFile {
+                path: file,
+                change: FileChange::Removed,
+            }),
+    );
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let sources_changed = serde_json::to_value(&settings.rule_sources).ok()
+        != serde_json::to_value(&current.rule_sources).ok();
+    let rules_update_needed = sources_changed
+        || files.iter().any(|file| {
+            file.change != FileChange::Unchanged
+                && (file.path.starts_with("local-rules/")
+                    || POLICY_FILES.contains(&file.path.as_str()))
+        });
+    let changed = files
+        .iter()
+        .filter(|file| file.change != FileChange::Unchanged)
+        .count();
+
+    let config = if dry_run {
+        None
+    } else {
+        // Rule updates read the local rules, policies and datasets, so none
+        // may run while they are replaced. A failed write puts back every
+        // file, including the generated ones.
+        let update_lock = app.state::<UpdateLock>();
+        let _guard = update_lock.try_acquire()?;
+        let mut snapshot = FileSnapshot::default();
+        for file in files
+            .iter()
+            .filter(|file| file.change != FileChange::Unchanged)
+        {
+            snapshot.add(local_path(&data_dir, &file.path))?;
+        }
+        snapshot.add(paths::datasets_include()?)?;
+        snapshot.add(paths::suricata_config()?)?;
+        snapshot.add(config::previous_path()?)?;
+
+        let written = (|| {
+            for file in &files {
+                let target = local_path(&data_dir, &file.path);
+                match file.change {
+                    FileChange::Unchanged => {}
+                    FileChange::Removed => std::fs::remove_file(&target)
+                        .map_err(|e| format!("Failed to remove {}: {}", file.path, e))?,
+                    FileChange::Added | FileChange::Modified => {
+                        if let Some(parent) = target.parent() {
+                            std::fs::create_dir_all(parent)
+                                .map_err(|e| format!("Failed to create directory: {}", e))?;
+                        }
+                        std::fs::write(&target, &entries[&file.path])
+                            .map_err(|e| format!("Failed to write {}: {}", file.path, e))?;
+                    }
+                }
+            }
+            datasets::regenerate_include()?;
+            config::generate(&settings.suricata_config)
+        })();
+        match written {
+            Ok(config) => {
+                http_client.replace(client);
+                Some(config)
+            }
+            Err(e) => {
+                return Err(match snapshot.restore() {
+                    Ok(()) => format!("{}, the configuration was left unchanged", e),
+                    Err(restore) => format!("{}. {}", e, restore),
+                })
+            }
+        }
+    };
+
+    let mut message = if dry_run {
+        format!("Importing {} would change {} files", path, changed)
+    } else {
+        format!("Imported {}, {} files changed", path, changed)
+    };
+    if rules_update_needed {
+        message.push_str(", update the rules to apply the rule sources, policies and local rules");
+    }
+    Ok(ConfigImport {
+        dry_run,
+        app_version: manifest.app_version,
+        created_at: manifest.created_at,
+        files,
+        config,
+        rules_update_needed,
+        message,
+    })
+}
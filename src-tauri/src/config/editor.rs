@@ -0,0 +1,636 @@
+use serde_json::{Map, Value};
+
+// Line based editing of suricata.yaml. Only the lines holding the edited key
+// are rewritten, so comments, formatting and the %YAML header are kept.
+
+struct Line<'a> {
+    text: &'a str,
+    // Spaces before the content, the dash of a list item included
+    leading: usize,
+    // Line starts with "- "
+    item: bool,
+    key: Option<&'a str>,
+    // Inline value after the key, or the content of a list item without a
+    // key, with any comment removed
+    value: Option<&'a str>,
+    // Blank lines, comments and document markers are not significant
+    significant: bool,
+}
+
+// Position of a trailing comment, ignoring # inside quotes
+fn comment_start(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous == ' ' || i == 0 => return Some(i),
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+// Split "key: value" or "key:", returning None for plain scalars
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    if content.starts_with(['"', '\'', '[', '{']) {
+        return None;
+    }
+    if let Some(key) = content.strip_suffix(':') {
+        return Some((key, ""));
+    }
+    let (key, value) = content.split_once(": ")?;
+    Some((key, value.trim()))
+}
+
+fn parse_line(text: &str) -> Line<'_> {
+    let trimmed = text.trim_start();
+    let leading = text.len() - trimmed.len();
+    let content = match comment_start(trimmed) {
+        Some(i) => trimmed[..i].trim_end(),
+        None => trimmed.trim_end(),
+    };
+    let significant = !content.is_empty() && !content.starts_with('%') && content != "---";
+
+    let (item, content) = match content.strip_prefix("- ") {
+        Some(rest) => (true, rest.trim_start()),
+        None if content == "-" => (true, ""),
+        None => (false, content),
+    };
+    let (key, value) = match split_key(content) {
+        Some((key, value)) => (Some(key.trim()), Some(value).filter(|v| !v.is_empty())),
+        None => (None, Some(content).filter(|v| !v.is_empty())),
+    };
+
+    Line {
+        text,
+        leading,
+        item,
+        key,
+        value,
+        significant,
+    }
+}
+
+// A key or list item found in the document
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    line: usize,
+    // Column the node's content starts at, after the dash of a list item
+    column: usize,
+    // End of the node's block, exclusive
+    end: usize,
+    // A list item rather than a key
+    item: bool,
+}
+
+struct Document<'a> {
+    lines: Vec<Line<'a>>,
+}
+
+impl<'a> Document<'a> {
+    fn parse(text: &'a str) -> Self {
+        Self {
+            lines: text.lines().map(parse_line).collect(),
+        }
+    }
+
+    // First line after `line` at or left of column, the end of its block
+    fn block_end(&self, line: usize, column: usize, end: usize) -> usize {
+        (line + 1..end)
+            .find(|i| self.lines[*i].significant && self.lines[*i].leading <= column)
+            .unwrap_or(end)
+    }
+
+    // End of a key's block. A list may sit at the key's own indentation, as
+    // in "outputs:" followed by "- console:" at the same column.
+    fn key_end(&self, line: usize, column: usize, end: usize) -> usize {
+        let next = (line + 1..end).find(|i| self.lines[*i].significant);
+        let compact = self.lines[line].value.is_none()
+            && next.is_some_and(|i| self.lines[i].item && self.lines[i].leading == column);
+        (line + 1..end)
+            .find(|i| {
+                let l = &self.lines[*i];
+                l.significant && (l.leading < column || l.leading == column && !(compact && l.item))
+            })
+            .unwrap_or(end)
+    }
+
+    // One past the last significant line of a block, so comments and blank
+    // lines before the next key stay with it
+    fn content_end(&self, start: usize, end: usize) -> usize {
+        (start..end)
+            .rev()
+            .find(|i| self.lines[*i].significant)
+            .map(|i| i + 1)
+            .unwrap_or(start)
+    }
+
+    // Lines holding the children of a node. A list item's mapping starts on
+    // the item's own line.
+    fn block(&self, node: Option<&Node>) -> (usize, usize, bool) {
+        match node {
+            Some(node) if node.item => (node.line, node.end, true),
+            Some(node) => (node.line + 1, node.end, false),
+            None => (0, self.lines.len(), false),
+        }
+    }
+
+    // Direct children of a node
+    fn children(&self, node: Option<&Node>) -> Vec<Node> {
+        let (start, end, first_item) = self.block(node);
+        let column = |i: usize| {
+            if first_item && i == start {
+                self.lines[i].leading + 2
+            } else {
+                self.lines[i].leading
+            }
+        };
+        let significant: Vec<usize> = (start..end)
+            .filter(|i| self.lines[*i].significant)
+            .collect();
+        let Some(level) = significant.iter().map(|i| column(*i)).min() else {
+            return Vec::new();
+        };
+
+        let mut nodes = Vec::new();
+        for i in significant {
+            if column(i) != level {
+                continue;
+            }
+            let line = &self.lines[i];
+            if line.item && !(first_item && i == start) {
+                nodes.push(Node {
+                    line: i,
+                    column: line.leading + 2,
+                    end: self.block_end(i, line.leading, end),
+                    item: true,
+                });
+            } else if line.key.is_some() {
+                nodes.push(Node {
+                    line: i,
+                    column: column(i),
+                    end: self.key_end(i, column(i), end),
+                    item: false,
+                });
+            }
+        }
+        nodes
+    }
+
+    // The mapping held by a list item like "- eve-log:", which Suricata also
+    // reaches through the item itself, as in outputs.1.enabled
+    fn keyed_mapping(&self, node: &Node) -> Option<Node> {
+        let line = &self.lines[node.line];
+        if !node.item || line.value.is_some() {
+            return None;
+        }
+        self.child(Some(node), line.key?)
+    }
+
+    // Child of a node by key, or by index when it is a list. The key of a
+    // list item holding a mapping also matches, as in outputs.eve-log.
+    fn child(&self, parent: Option<&Node>, segment: &str) -> Option<Node> {
+        if let Some(mapping) = parent
+            .filter(|parent| self.lines[parent.line].key != Some(segment))
+            .and_then(|parent| self.keyed_mapping(parent))
+        {
+            return self.child(Some(&mapping), segment);
+        }
+        let children = self.children(parent);
+        if let Ok(index) = segment.parse::<usize>() {
+            if let Some(node) = children.iter().filter(|node| node.item).nth(index) {
+                return Some(*node);
+            }
+        }
+        let node = children
+            .into_iter()
+            .find(|node| self.lines[node.line].key == Some(segment))?;
+        if node.item {
+            // The keyed item's own mapping continues below the item line
+            return Some(Node {
+                end: self.key_end(node.line, node.column, node.end),
+                item: false,
+                ..node
+            });
+        }
+        Some(node)
+    }
+
+    fn resolve(&self, path: &[&str]) -> Option<Node> {
+        let mut node = None;
+        for segment in path {
+            node = Some(self.child(node.as_ref(), segment)?);
+        }
+        node
+    }
+
+    // A list item without a key
+    fn is_bare_item(&self, node: &Node) -> bool {
+        node.item && self.lines[node.line].key.is_none()
+    }
+
+    // The children are all list items
+    fn is_list(&self, children: &[Node]) -> bool {
+        !children.is_empty() && children.iter().all(|child| child.item)
+    }
+
+    fn value(&self, node: &Node) -> Value {
+        let line = &self.lines[node.line];
+        if self.is_bare_item(node) {
+            return line.value.map(scalar).unwrap_or(Value::Null);
+        }
+        if !node.item {
+            if let Some(value) = line.value {
+                return scalar(value);
+            }
+        }
+        let children = self.children(Some(node));
+        if children.is_empty() {
+            return Value::Null;
+        }
+        if self.is_list(&children) {
+            return Value::Array(children.iter().map(|child| self.value(child)).collect());
+        }
+        let mut map = Map::new();
+        for child in &children {
+            if let Some(key) = self.lines[child.line].key {
+                map.insert(key.to_string(), self.value(child));
+            }
+        }
+        Value::Object(map)
+    }
+}
+
+// A scalar as written in the file, without quotes
+fn scalar(value: &str) -> Value {
+    let unquoted = if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        let inner = &value[1..value.len() - 1];
+        if value.starts_with('"') {
+            inner.replace("\\\"", "\"").replace("\\\\", "\\")
+        } else {
+            inner.replace("''", "'")
+        }
+    } else {
+        value.to_string()
+    };
+    Value::String(unquoted)
+}
+
+// Write a value as a yaml scalar, quoting it when it would otherwise be read
+// as something else
+pub fn render_scalar(value: &Value) -> Result<String, String> {
+    let text = match value {
+        Value::Bool(true) => return Ok("yes".to_string()),
+        Value::Bool(false) => return Ok("no".to_string()),
+        Value::Number(number) => return Ok(number.to_string()),
+        Value::String(text) => text,
+        _ => return Err("Expected a string, number or boolean".to_string()),
+    };
+    if text.contains('\n') {
+        return Err("Values must be on a single line".to_string());
+    }
+    let needs_quotes = text.is_empty()
+        || text.trim() != text
+        || text.starts_with([
+            '[', ']', '{', '}', '!', '&', '*', '\'', '"', '%', '@', '`', '|', '>', '-', '?', '#',
+            ',',
+        ])
+        || text.contains(": ")
+        || text.contains(" #")
+        || text.ends_with(':');
+    if needs_quotes {
+        Ok(format!(
+            "\"{}\"",
+            text.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
+    } else {
+        Ok(text.clone())
+    }
+}
+
+fn split_path(path: &str) -> Result<Vec<&str>, String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|segment| segment.trim().is_empty()) {
+        return Err(format!("Invalid configuration key {}", path));
+    }
+    Ok(segments)
+}
+
+// Value at a dotted path, sections are returned as objects and lists as
+// arrays
+pub fn get(text: &str, path: &str) -> Result<Option<Value>, String> {
+    let segments = split_path(path)?;
+    let document = Document::parse(text);
+    Ok(document
+        .resolve(&segments)
+        .map(|node| document.value(&node)))
+}
+
+// Lines of a list, one scalar per item
+fn render_list(items: &[Value], indent: usize) -> Result<Vec<String>, String> {
+    items
+        .iter()
+        .map(|item| Ok(format!("{}- {}", " ".repeat(indent), render_scalar(item)?)))
+        .collect()
+}
+
+// Set a scalar or a list of scalars at a dotted path, adding missing keys
+pub fn set(text: &str, path: &str, value: &Value) -> Result<String, String> {
+    let segments = split_path(path)?;
+    let document = Document::parse(text);
+    let mut lines: Vec<String> = document.lines.iter().map(|l| l.text.to_string()).collect();
+
+    // Walk down as far as the document goes
+    let mut node: Option<Node> = None;
+    let mut found = 0;
+    for segment in &segments {
+        match document.child(node.as_ref(), segment) {
+            Some(child) => {
+                node = Some(child);
+                found += 1;
+            }
+            None => break,
+        }
+    }
+
+    if found < segments.len() {
+        let missing = &segments[found..];
+        if missing[0].parse::<usize>().is_ok() {
+            return Err(format!("No list item {} in {}", missing[0], path));
+        }
+        // Keys added to a keyed list item go into its mapping
+        if let Some(mapping) = node.and_then(|node| document.keyed_mapping(&node)) {
+            node = Some(mapping);
+        }
+        if let Some(parent) = &node {
+            let holds_value = !parent.item && document.lines[parent.line].value.is_some();
+            let children = document.children(Some(parent));
+            if holds_value || document.is_bare_item(parent) || document.is_list(&children) {
+                return Err(format!(
+                    "{} holds a value, it can't have keys",
+                    segments[..found].join(".")
+                ));
+            }
+        }
+
+        // New keys line up with their siblings, or are indented below the
+        // parent when it has none
+        let indent = match document.children(node.as_ref()).first() {
+            Some(sibling) => sibling.column,
+            None => match &node {
+                Some(parent) if parent.item => parent.column,
+                Some(parent) => parent.column + 2,
+                None => 0,
+            },
+        };
+        let insert_at = match &node {
+            Some(parent) => document
+                .content_end(parent.line, parent.end)
+                .max(parent.line + 1),
+            None => document.content_end(0, lines.len()),
+        };
+
+        let mut new_lines = Vec::new();
+        for (depth, segment) in missing.iter().enumerate() {
+            let pad = " ".repeat(indent + depth * 2);
+            if depth + 1 < missing.len() {
+                new_lines.push(format!("{}{}:", pad, segment));
+            } else if let Value::Array(items) = value {
+                if items.is_empty() {
+                    new_lines.push(format!("{}{}: []", pad, segment));
+                } else {
+                    new_lines.push(format!("{}{}:", pad, segment));
+                    new_lines.extend(render_list(items, indent + depth * 2 + 2)?);
+                }
+            } else {
+                new_lines.push(format!("{}{}: {}", pad, segment, render_scalar(value)?));
+            }
+        }
+        lines.splice(insert_at..insert_at, new_lines);
+        return Ok(join(lines, text));
+    }
+
+    let node = node.ok_or_else(|| "Empty configuration key".to_string())?;
+    let line = &document.lines[node.line];
+
+    if document.is_bare_item(&node) {
+        if let Value::Array(_) = value {
+            return Err(format!("{} is a list item, set it to a single value", path));
+        }
+        let prefix = &line.text[..line.leading + 2];
+        lines[node.line] = format!("{}{}", prefix, render_scalar(value)?);
+        return Ok(join(lines, text));
+    }
+    if node.item {
+        return Err(format!(
+            "{} is a section, set one of its keys instead",
+            path
+        ));
+    }
+
+    // Everything up to and including the colon after the key. The key starts
+    // the line's content, after the dash of a list item.
+    let key = line.key.unwrap_or("");
+    let mut key_start = line.leading;
+    if line.item {
+        let rest = &line.text[key_start + 1..];
+        key_start += 1 + rest.len() - rest.trim_start().len();
+    }
+    let key_end = line.text[key_start + key.len()..]
+        .find(':')
+        .map(|i| key_start + key.len() + i + 1)
+        .unwrap_or(line.text.len());
+    let prefix = &line.text[..key_end];
+    let comment = comment_start(&line.text[line.leading..])
+        .map(|i| format!(" {}", &line.text[line.leading + i..]))
+        .unwrap_or_default();
+    let children = document.children(Some(&node));
+    let block_end = document.content_end(node.line + 1, node.end);
+
+    match value {
+        Value::Array(items) => {
+            if !children.is_empty() && !document.is_list(&children) {
+                return Err(format!(
+                    "{} is a section, set one of its keys instead",
+                    path
+                ));
+            }
+            let indent = children
+                .first()
+                .map(|child| child.column - 2)
+                .unwrap_or(node.column + 2);
+            let mut new_lines = Vec::new();
+            if items.is_empty() {
+                new_lines.push(format!("{} []{}", prefix, comment));
+            } else {
+                new_lines.push(format!("{}{}", prefix, comment));
+                new_lines.extend(render_list(items, indent)?);
+            }
+            lines.splice(node.line..block_end.max(node.line + 1), new_lines);
+        }
+        _ => {
+            if !children.is_empty() {
+                return Err(format!(
+                    "{} is a section, set one of its keys instead",
+                    path
+                ));
+            }
+            lines[node.line] = format!("{} {}{}", prefix, render_scalar(value)?, comment);
+        }
+    }
+    Ok(join(lines, text))
+}
+
+fn join(lines: Vec<String>, original: &str) -> String {
+    let mut text = lines.join("\n");
+    if original.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{render, SuricataConfigSettings};
+    use serde_json::json;
+
+    fn template() -> String {
+        render(&SuricataConfigSettings::default())
+    }
+
+    fn get_value(text: &str, path: &str) -> Value {
+        get(text, path).unwrap().unwrap()
+    }
+
+    #[test]
+    fn get_eve_types() {
+        let types = SuricataConfigSettings::default().eve_types;
+        assert_eq!(
+            get_value(&template(), "outputs.eve-log.types"),
+            json!(types)
+        );
+    }
+
+    #[test]
+    fn get_keyed_item_by_index() {
+        let text = template();
+        assert_eq!(get_value(&text, "outputs.1.enabled"), json!("yes"));
+        assert_eq!(get_value(&text, "outputs.1.filename"), json!("eve.json"));
+        assert_eq!(
+            get_value(&text, "outputs.1.eve-log.filetype"),
+            json!("regular")
+        );
+        assert_eq!(get_value(&text, "outputs.0.filename"), json!("fast.log"));
+    }
+
+    #[test]
+    fn get_missing_key() {
+        assert_eq!(get(&template(), "outputs.eve-log.missing").unwrap(), None);
+        assert!(get(&template(), "outputs..enabled").is_err());
+    }
+
+    #[test]
+    fn set_eve_types() {
+        let text = set(
+            &template(),
+            "outputs.eve-log.types",
+            &json!(["alert", "dns"]),
+        )
+        .unwrap();
+        assert_eq!(
+            get_value(&text, "outputs.eve-log.types"),
+            json!(["alert", "dns"])
+        );
+        assert_eq!(
+            get_value(&text, "outputs.eve-log.filename"),
+            json!("eve.json")
+        );
+        assert_eq!(
+            get_value(&text, "logging.default-log-level"),
+            json!("notice")
+        );
+    }
+
+    #[test]
+    fn set_keyed_item_by_index() {
+        let text = set(&template(), "outputs.1.enabled", &json!(false)).unwrap();
+        assert_eq!(get_value(&text, "outputs.eve-log.enabled"), json!("no"));
+        assert_eq!(get_value(&text, "outputs.fast.enabled"), json!("yes"));
+        assert!(text.contains("  - eve-log:\n      enabled: no\n"));
+    }
+
+    #[test]
+    fn set_compact_list_item() {
+        let text = set(&template(), "logging.outputs.1.level", &json!("warning")).unwrap();
+        assert_eq!(
+            get_value(&text, "logging.outputs.file.level"),
+            json!("warning")
+        );
+        assert_eq!(
+            get_value(&text, "logging.outputs.console.enabled"),
+            json!("yes")
+        );
+    }
+
+    #[test]
+    fn set_adds_nested_key() {
+        let path = "app-layer.protocols.http.libhtp.default-config.personality";
+        let text = set(&template(), path, &json!("IDS")).unwrap();
+        assert_eq!(get_value(&text, path), json!("IDS"));
+        assert_eq!(
+            get_value(&text, "app-layer.protocols.http.enabled"),
+            json!("yes")
+        );
+        assert_eq!(
+            get_value(&text, "app-layer.protocols.http2.enabled"),
+            json!("yes")
+        );
+        assert!(text.contains(
+            "    http:\n      enabled: yes\n      libhtp:\n        default-config:\n          personality: IDS\n"
+        ));
+    }
+
+    #[test]
+    fn set_adds_key_to_keyed_item() {
+        let text = set(&template(), "outputs.1.xff.enabled", &json!("no")).unwrap();
+        assert_eq!(get_value(&text, "outputs.eve-log.xff.enabled"), json!("no"));
+        assert_eq!(
+            get_value(&text, "outputs.eve-log.types"),
+            get_value(&template(), "outputs.eve-log.types")
+        );
+    }
+
+    #[test]
+    fn set_keeps_header_and_comments() {
+        let original = template();
+        let text = set(&original, "host-mode", &json!("sniffer-only")).unwrap();
+        assert!(text.starts_with("%YAML 1.1\n---\n"));
+        for line in original.lines().filter(|line| line.trim().starts_with('#')) {
+            assert!(text.contains(line), "lost comment {}", line);
+        }
+        assert_eq!(text.lines().count(), original.lines().count());
+    }
+
+    #[test]
+    fn set_keeps_trailing_comment() {
+        let text = "a:\n  b: 1 # note\n  c: 2\n";
+        assert_eq!(
+            set(text, "a.b", &json!(3)).unwrap(),
+            "a:\n  b: 3 # note\n  c: 2\n"
+        );
+    }
+
+    #[test]
+    fn set_rejects_sections() {
+        assert!(set(&template(), "outputs.eve-log", &json!("yes")).is_err());
+        assert!(set(&template(), "detect.custom-values", &json!(1)).is_err());
+        assert!(set(&template(), "outputs.5.enabled", &json!("yes")).is_err());
+    }
+}
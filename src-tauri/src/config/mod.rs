@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::paths;
+use crate::settings;
+use crate::threshold;
+
+mod editor;
+pub mod overrides;
+mod schema;
+
+use overrides::Overrides;
+
+const TEMPLATE: &str = include_str!("../../templates/suricata.yaml");
+
+// Lines of unchanged context shown around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+// Event types the eve-log output can write
+const EVE_TYPES: &[&str] = &[
+    "alert",
+    "anomaly",
+    "http",
+    "dns",
+    "tls",
+    "files",
+    "smtp",
+    "ftp",
+    "rdp",
+    "nfs",
+    "smb",
+    "tftp",
+    "ike",
+    "dcerpc",
+    "krb5",
+    "bittorrent-dht",
+    "snmp",
+    "rfb",
+    "sip",
+    "quic",
+    "dhcp",
+    "ssh",
+    "mqtt",
+    "http2",
+    "pgsql",
+    "flow",
+    "netflow",
+    "stats",
+];
+
+// App-layer parsers that can be turned on and off, with whether Suricata
+// enables them by default
+const APP_LAYER_PROTOCOLS: &[(&str, bool)] = &[
+    ("http", true),
+    ("http2", true),
+    ("tls", true),
+    ("dns", true),
+    ("smtp", true),
+    ("ssh", true),
+    ("smb", true),
+    ("ftp", true),
+    ("dcerpc", true),
+    ("nfs", true),
+    ("tftp", true),
+    ("krb5", true),
+    ("dhcp", true),
+    ("snmp", true),
+    ("sip", true),
+    ("rdp", true),
+    ("mqtt", true),
+    ("quic", true),
+    ("ike", true),
+    ("rfb", true),
+    ("bittorrent-dht", true),
+    ("pgsql", false),
+    ("modbus", false),
+    ("dnp3", false),
+    ("enip", false),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuricataConfigSettings {
+    // Addresses and CIDRs of the monitored network
+    pub home_net: Vec<String>,
+    pub external_net: String,
+    // Event types written to eve.json
+    pub eve_types: Vec<String>,
+    // App-layer parsers by name, missing ones keep Suricata's default
+    pub app_layer: BTreeMap<String, bool>,
+}
+
+impl Default for SuricataConfigSettings {
+    fn default() -> Self {
+        Self {
+            home_net: vec![
+                "192.168.0.0/16".to_string(),
+                "10.0.0.0/8".to_string(),
+                "172.16.0.0/12".to_string(),
+            ],
+            external_net: "!$HOME_NET".to_string(),
+            eve_types: [
+                "alert", "anomaly", "http", "dns", "tls", "files", "smtp", "ssh", "flow", "stats",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+            app_layer: BTreeMap::new(),
+        }
+    }
+}
+
+impl SuricataConfigSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.home_net.is_empty() {
+            return Err("HOME_NET must contain at least one address".to_string());
+        }
+        for address in &self.home_net {
+            if address.trim_start().starts_with('!') {
+                return Err(format!(
+                    "HOME_NET can't contain negated addresses: {}",
+                    address
+                ));
+            }
+            threshold::validate_address(address)
+                .map_err(|e| format!("Invalid HOME_NET entry: {}", e))?;
+        }
+        threshold::validate_ip_spec(&self.external_net)
+            .map_err(|e| format!("Invalid EXTERNAL_NET: {}", e))?;
+        for eve_type in &self.eve_types {
+            if !EVE_TYPES.contains(&eve_type.as_str()) {
+                return Err(format!("Unknown eve-log event type {}", eve_type));
+            }
+        }
+        for protocol in self.app_layer.keys() {
+            if !APP_LAYER_PROTOCOLS.iter().any(|(name, _)| name == protocol) {
+                return Err(format!("Unknown app-layer protocol {}", protocol));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+// A run of changes with the context around it. Line numbers are 1-based.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedConfig {
+    pub path: String,
+    // False when the generated configuration is the same as before
+    pub changed: bool,
+    // Changes against the previously generated configuration
+    pub diff: Vec<DiffHunk>,
+}
+
+pub fn previous_path() -> Result<PathBuf, String> {
+    Ok(paths::config_dir()?.join("suricata.yaml.previous"))
+}
+
+// Quote a value for a double quoted yaml scalar
+fn yaml_quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn render(config: &SuricataConfigSettings) -> String {
+    let home_net = format!("[{}]", config.home_net.join(","));
+
+    let eve_types: Vec<String> = config
+        .eve_types
+        .iter()
+        .map(|eve_type| format!("        - {}", eve_type))
+        .collect();
+
+    let app_layer: Vec<String> = APP_LAYER_PROTOCOLS
+        .iter()
+        .map(|(name, default)| {
+            let enabled = config.app_layer.get(*name).copied().unwrap_or(*default);
+            format!(
+                "    {}:\n      enabled: {}",
+                name,
+                if enabled { "yes" } else { "no" }
+            )
+        })
+        .collect();
+
+    TEMPLATE
+        .replace("{{HOME_NET}}", &yaml_quote(&home_net))
+        .replace("{{EXTERNAL_NET}}", &yaml_quote(&config.external_net))
+        .replace("{{EVE_TYPES}}", &eve_types.join("\n"))
+        .replace("{{APP_LAYER}}", &app_layer.join("\n"))
+        .replace("{{SURICATA_DIR}}", paths::SURICATA_DIR)
+}
+
+// Line diff of two texts, grouped into hunks with some context
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffHunk> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, lcs[i][j] covers old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Every line with its position in the old and new text
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((DiffKind::Context, i, j, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push((DiffKind::Removed, i, j, old[i]));
+            i += 1;
+        } else {
+            lines.push((DiffKind::Added, i, j, new[j]));
+            j += 1;
+        }
+    }
+
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut end = 0;
+    for (index, (kind, _, _, _)) in lines.iter().enumerate() {
+        if *kind == DiffKind::Context {
+            continue;
+        }
+        let start = index.saturating_sub(DIFF_CONTEXT).max(end);
+        let stop = (index + DIFF_CONTEXT + 1).min(lines.len());
+        // Start a new hunk unless this change is close to the last one
+        if hunks.is_empty() || start > end {
+            let (_, old_line, new_line, _) = lines[start];
+            hunks.push(DiffHunk {
+                old_start: old_line + 1,
+                new_start: new_line + 1,
+                lines: Vec::new(),
+            });
+        }
+        let hunk = hunks.last_mut().unwrap();
+        for (kind, _, _, text) in &lines[start.max(end)..stop] {
+            hunk.lines.push(DiffLine {
+                kind: *kind,
+                text: text.to_string(),
+            });
+        }
+        end = end.max(stop);
+    }
+    hunks
+}
+
+// Path of the configuration Suricata is run with, the generated one when it
+// exists and the installed default otherwise
+pub fn active_path() -> Result<PathBuf, String> {
+    let generated = paths::suricata_config()?;
+    if generated.exists() {
+        Ok(generated)
+    } else {
+        Ok(PathBuf::from(paths::SURICATA_DIR).join("suricata.yaml"))
+    }
+}
+
+// Build suricata.yaml from the template, settings and editor overrides
+// without writing it, so settings can be checked before they are saved
+pub fn build(config: &SuricataConfigSettings, overrides: &Overrides) -> Result<String, String> {
+    overrides::apply(&render(config), overrides)
+}
+
+// Write suricata.yaml from the template, settings and editor overrides,
+// keeping the previous version when it changes
+pub fn generate(config: &SuricataConfigSettings) -> Result<GeneratedConfig, String> {
+    let path = paths::suricata_config()?;
+    let dir = paths::config_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+
+    // Keys set in the editor are applied on top of the template
+    let content = build(config, &overrides::load()?)?;
+    let current = std::fs::read_to_string(&path).ok();
+    let changed = current.as_deref() != Some(content.as_str());
+    let diff = diff_lines(current.as_deref().unwrap_or(""), &content);
+
+    if changed {
+        if current.is_some() {
+            std::fs::rename(&path, previous_path()?)
+                .map_err(|e| format!("Failed to keep previous suricata.yaml: {}", e))?;
+        }
+        std::fs::write(&path, &content)
+            .map_err(|e| format!("Failed to write suricata.yaml: {}", e))?;
+    }
+
+    Ok(GeneratedConfig {
+        path: path.display().to_string(),
+        changed,
+        diff,
+    })
+}
+
+#[tauri::command]
+pub fn generate_suricata_config() -> Result<GeneratedConfig, String> {
+    generate(&settings::load()?.suricata_config)
+}
+
+#[tauri::command]
+pub fn get_suricata_config() -> Result<String, String> {
+    let path = paths::suricata_config()?;
+    if !path.exists() {
+        generate(&settings::load()?.suricata_config)?;
+    }
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read suricata.yaml: {}", e))
+}
+
+// Changes between the previous and current generated configuration
+#[tauri::command]
+pub fn get_suricata_config_diff() -> Result<Vec<DiffHunk>, String> {
+    let previous = std::fs::read_to_string(previous_path()?).unwrap_or_default();
+    let current = std::fs::read_to_string(paths::suricata_config()?).unwrap_or_default();
+    Ok(diff_lines(&previous, &current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(hunk: &DiffHunk) -> Vec<(DiffKind, &str)> {
+        hunk.lines
+            .iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_identical() {
+        assert!(diff_lines("a\nb\nc\n", "a\nb\nc\n").is_empty());
+    }
+
+    #[test]
+    fn diff_changed_line() {
+        let hunks = diff_lines("a\nb\nc\nd\ne\nf\n", "a\nb\nc\nX\ne\nf\n");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].old_start, 1);
+        assert_eq!(hunks[0].new_start, 1);
+        assert_eq!(
+            kinds(&hunks[0]),
+            vec![
+                (DiffKind::Context, "a"),
+                (DiffKind::Context, "b"),
+                (DiffKind::Context, "c"),
+                (DiffKind::Removed, "d"),
+                (DiffKind::Added, "X"),
+                (DiffKind::Context, "e"),
+                (DiffKind::Context, "f"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_separate_hunks() {
+        let old: Vec<String> = (1..=20).map(|i| i.to_string()).collect();
+        let mut new = old.clone();
+        new[1] = "two".to_string();
+        new.insert(15, "extra".to_string());
+        let hunks = diff_lines(&old.join("\n"), &new.join("\n"));
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].new_start), (1, 1));
+        assert_eq!((hunks[1].old_start, hunks[1].new_start), (13, 13));
+        assert_eq!(
+            kinds(&hunks[1])
+                .iter()
+                .filter(|(kind, _)| *kind != DiffKind::Context)
+                .collect::<Vec<_>>(),
+            vec![&(DiffKind::Added, "extra")]
+        );
+    }
+
+    #[test]
+    fn diff_from_empty() {
+        let hunks = diff_lines("", "a\nb\n");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(
+            kinds(&hunks[0]),
+            vec![(DiffKind::Added, "a"), (DiffKind::Added, "b")]
+        );
+    }
+
+    #[test]
+    fn rendered_template_has_no_placeholders() {
+        let text = render(&SuricataConfigSettings::default());
+        assert!(!text.contains("{{"));
+        assert!(text.contains("HOME_NET: \"[192.168.0.0/16,10.0.0.0/8,172.16.0.0/12]\""));
+    }
+}
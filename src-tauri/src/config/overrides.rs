@@ -0,0 +1,253 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+use super::{editor, schema, GeneratedConfig};
+use crate::paths;
+use crate::settings;
+
+// Only this many lines of Suricata error output are kept
+const MAX_ERRORS: usize = 50;
+
+// Keys set through the editor, applied on top of every generated config so
+// they survive regeneration
+pub type Overrides = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigTest {
+    // True when Suricata is not installed and the config wasn't tested
+    pub skipped: bool,
+    pub ok: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub previous: Option<Value>,
+    pub value: Option<Value>,
+    pub test: ConfigTest,
+    pub config: GeneratedConfig,
+}
+
+fn overrides_path() -> Result<PathBuf, String> {
+    Ok(paths::config_dir()?.join("overrides.json"))
+}
+
+pub fn load() -> Result<Overrides, String> {
+    let path = overrides_path()?;
+    if !path.exists() {
+        return Ok(Overrides::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read config overrides: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse config overrides: {}", e))
+}
+
+pub fn save(overrides: &Overrides) -> Result<(), String> {
+    std::fs::create_dir_all(paths::config_dir()?)
+        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    let content = serde_json::to_string_pretty(overrides)
+        .map_err(|e| format!("Failed to serialize config overrides: {}", e))?;
+    std::fs::write(overrides_path()?, content)
+        .map_err(|e| format!("Failed to write config overrides: {}", e))
+}
+
+// Check the overrides' values against their key types
+pub fn validate(overrides: &Overrides) -> Result<(), String> {
+    for (path, value) in overrides {
+        schema::validate(path, value)?;
+    }
+    Ok(())
+}
+
+// Apply the overrides to a generated config
+pub fn apply(text: &str, overrides: &Overrides) -> Result<String, String> {
+    let mut text = text.to_string();
+    for (path, value) in overrides {
+        text = editor::set(&text, path, value)
+            .map_err(|e| format!("Failed to apply config override {}: {}", path, e))?;
+    }
+    Ok(text)
+}
+
+async fn run_suricata(args: &[&std::ffi::OsStr]) -> Result<(bool, Vec<String>), String> {
+    let mut cmd = TokioCommand::new(paths::suricata_exe());
+    cmd.current_dir(paths::SURICATA_DIR).args(args);
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run Suricata: {}", e))?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let errors = text
+        .lines()
+        .map(|line| crate::strip_ansi_codes(line.trim()))
+        .filter(|line| line.to_lowercase().contains("error"))
+        .take(MAX_ERRORS)
+        .collect();
+    Ok((output.status.success(), errors))
+}
+
+// Check a config with suricata --dump-config, which fails on yaml Suricata
+// can't parse, then with -T, which loads the whole config and the rules
+pub async fn test_config(config_path: &Path) -> Result<ConfigTest, String> {
+    if !paths::suricata_exe().exists() {
+        return Ok(ConfigTest {
+            skipped: true,
+            ok: true,
+            errors: Vec::new(),
+        });
+    }
+
+    let (ok, errors) = run_suricata(&[
+        "--dump-config".as_ref(),
+        "-c".as_ref(),
+        config_path.as_os_str(),
+    ])
+    .await?;
+    if !ok {
+        return Ok(ConfigTest {
+            skipped: false,
+            ok,
+            errors,
+        });
+    }
+
+    let log_dir = paths::log_dir()?.join("config-test");
+    std::fs::create_dir_all(&log_dir)
+        .map_err(|e| format!("Failed to create test log directory: {}", e))?;
+    let rules_path = paths::rules_dir()?.join("suricata.rules");
+    let datasets_include = paths::datasets_include()?;
+    let mut args = vec![
+        "-T".as_ref(),
+        "-c".as_ref(),
+        config_path.as_os_str(),
+        "-l".as_ref(),
+        log_dir.as_os_str(),
+    ];
+    if rules_path.exists() {
+        args.extend(["-S".as_ref(), rules_path.as_os_str()]);
+    }
+    if datasets_include.exists() {
+        args.extend(["--include".as_ref(), datasets_include.as_os_str()]);
+    }
+    let (ok, errors) = run_suricata(&args).await?;
+    Ok(ConfigTest {
+        skipped: false,
+        ok,
+        errors,
+    })
+}
+
+// Build the config with the given overrides, test it and keep the overrides
+// only if Suricata accepts it
+async fn apply_overrides(
+    overrides: Overrides,
+    test: bool,
+) -> Result<(ConfigTest, GeneratedConfig), String> {
+    let config = settings::load()?.suricata_config;
+    let content = apply(&super::render(&config), &overrides)?;
+
+    let result = if test {
+        let dir = paths::config_dir()?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        let test_path = dir.join("suricata.yaml.test");
+        std::fs::write(&test_path, &content)
+            .map_err(|e| format!("Failed to write test config: {}", e))?;
+        let result = test_config(&test_path).await;
+        let _ = std::fs::remove_file(&test_path);
+        let result = result?;
+        if !result.ok {
+            return Err(format!(
+                "Suricata rejected the configuration: {}",
+                result.errors.join("\n")
+            ));
+        }
+        result
+    } else {
+        ConfigTest {
+            skipped: true,
+            ok: true,
+            errors: Vec::new(),
+        }
+    };
+
+    save(&overrides)?;
+    let generated = super::generate(&config)?;
+    Ok((result, generated))
+}
+
+#[tauri::command]
+pub fn get_config_value(path: String) -> Result<Option<Value>, String> {
+    let content = super::get_suricata_config()?;
+    editor::get(&content, &path)
+}
+
+// Set a key in suricata.yaml. The value is checked against the key's type
+// and, unless test is false, the resulting config is tested with Suricata
+// before it is saved.
+#[tauri::command]
+pub async fn set_config_value(
+    path: String,
+    value: Value,
+    test: Option<bool>,
+) -> Result<ConfigChange, String> {
+    schema::validate(&path, &value)?;
+    let previous = editor::get(&super::get_suricata_config()?, &path)?;
+
+    let mut overrides = load()?;
+    // A new value replaces any overrides below it
+    let nested = format!("{}.", path);
+    overrides.retain(|key, _| !key.starts_with(&nested));
+    overrides.insert(path.clone(), value.clone());
+
+    let (test, config) = apply_overrides(overrides, test.unwrap_or(true)).await?;
+    Ok(ConfigChange {
+        path,
+        previous,
+        value: Some(value),
+        test,
+        config,
+    })
+}
+
+// Drop an override, the key goes back to its generated value
+#[tauri::command]
+pub async fn remove_config_override(path: String) -> Result<ConfigChange, String> {
+    let previous = editor::get(&super::get_suricata_config()?, &path)?;
+    let mut overrides = load()?;
+    if overrides.remove(&path).is_none() {
+        return Err(format!("No override set for {}", path));
+    }
+    let (test, config) = apply_overrides(overrides, false).await?;
+    let value = editor::get(&super::get_suricata_config()?, &path)?;
+    Ok(ConfigChange {
+        path,
+        previous,
+        value,
+        test,
+        config,
+    })
+}
+
+#[tauri::command]
+pub fn list_config_overrides() -> Result<Overrides, String> {
+    load()
+}
+
+// Test the config Suricata is currently started with
+#[tauri::command]
+pub async fn test_suricata_config() -> Result<ConfigTest, String> {
+    test_config(&super::active_path()?).await
+}
@@ -0,0 +1,197 @@
+use serde_json::Value;
+
+use crate::threshold;
+
+// Types of the suricata.yaml keys values are checked against before saving.
+// Keys not listed here accept any scalar or list of scalars.
+#[derive(Debug, Clone, Copy)]
+enum KeyType {
+    Bool,
+    Integer,
+    Float,
+    // Byte sizes like 128mb or 1gb
+    Size,
+    Text,
+    // An address group like [$HOME_NET,10.0.0.0/8]
+    Addresses,
+    OneOf(&'static [&'static str]),
+    List,
+}
+
+// HOME_NET is set with set_home_net, an override would silently replace it
+const HOME_NET_KEY: &str = "vars.address-groups.HOME_NET";
+
+// Dotted key patterns, * matches any single key
+const KEY_TYPES: &[(&str, KeyType)] = &[
+    ("vars.address-groups.*", KeyType::Addresses),
+    ("vars.port-groups.*", KeyType::Text),
+    ("default-log-dir", KeyType::Text),
+    ("stats.enabled", KeyType::Bool),
+    ("stats.interval", KeyType::Integer),
+    ("outputs.*.enabled", KeyType::Bool),
+    ("outputs.*.filename", KeyType::Text),
+    ("outputs.*.append", KeyType::Bool),
+    (
+        "outputs.eve-log.filetype",
+        KeyType::OneOf(&["regular", "syslog", "unix_dgram", "unix_stream", "redis"]),
+    ),
+    ("outputs.eve-log.community-id", KeyType::Bool),
+    ("outputs.eve-log.types", KeyType::List),
+    (
+        "logging.default-log-level",
+        KeyType::OneOf(&[
+            "error", "warning", "notice", "info", "perf", "config", "debug",
+        ]),
+    ),
+    (
+        "app-layer.protocols.*.enabled",
+        KeyType::OneOf(&["yes", "no", "true", "false", "detection-only"]),
+    ),
+    (
+        "host-mode",
+        KeyType::OneOf(&["auto", "router", "sniffer-only"]),
+    ),
+    (
+        "detect.profile",
+        KeyType::OneOf(&["low", "medium", "high", "custom"]),
+    ),
+    ("detect.custom-values.*", KeyType::Integer),
+    ("detect.inspection-recursion-limit", KeyType::Integer),
+    (
+        "mpm-algo",
+        KeyType::OneOf(&["auto", "ac", "ac-bs", "ac-ks", "hs"]),
+    ),
+    ("spm-algo", KeyType::OneOf(&["auto", "bm", "hs"])),
+    ("threading.set-cpu-affinity", KeyType::Bool),
+    ("threading.detect-thread-ratio", KeyType::Float),
+    ("flow.memcap", KeyType::Size),
+    ("flow.hash-size", KeyType::Integer),
+    ("flow.prealloc", KeyType::Integer),
+    ("flow.emergency-recovery", KeyType::Integer),
+    ("stream.memcap", KeyType::Size),
+    ("stream.checksum-validation", KeyType::Bool),
+    (
+        "stream.inline",
+        KeyType::OneOf(&["auto", "yes", "no", "true", "false"]),
+    ),
+    ("stream.reassembly.memcap", KeyType::Size),
+    ("stream.reassembly.depth", KeyType::Size),
+    ("stream.reassembly.toserver-chunk-size", KeyType::Size),
+    ("stream.reassembly.toclient-chunk-size", KeyType::Size),
+    ("stream.reassembly.randomize-chunk-size", KeyType::Bool),
+    ("host.memcap", KeyType::Size),
+    ("host.hash-size", KeyType::Integer),
+    ("host.prealloc", KeyType::Integer),
+    ("defrag.memcap", KeyType::Size),
+    ("defrag.hash-size", KeyType::Integer),
+    ("defrag.trackers", KeyType::Integer),
+    ("defrag.max-frags", KeyType::Integer),
+    ("defrag.prealloc", KeyType::Bool),
+    ("defrag.timeout", KeyType::Integer),
+];
+
+const BOOL_VALUES: &[&str] = &["yes", "no", "true", "false", "on", "off", "1", "0"];
+
+fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let path: Vec<&str> = path.split('.').collect();
+    pattern.len() == path.len()
+        && pattern
+            .iter()
+            .zip(&path)
+            .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+}
+
+fn is_size(value: &str) -> bool {
+    let value = value.to_lowercase();
+    let digits = value
+        .strip_suffix("kb")
+        .or_else(|| value.strip_suffix("mb"))
+        .or_else(|| value.strip_suffix("gb"))
+        .unwrap_or(&value);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(if *flag { "yes" } else { "no" }.to_string()),
+        _ => None,
+    }
+}
+
+fn check(key_type: KeyType, path: &str, value: &Value) -> Result<(), String> {
+    if let KeyType::List = key_type {
+        return match value {
+            Value::Array(items) if items.iter().all(|item| scalar_text(item).is_some()) => Ok(()),
+            _ => Err(format!("{} must be a list of values", path)),
+        };
+    }
+    let text = scalar_text(value).ok_or_else(|| format!("{} must be a single value", path))?;
+    let valid = match key_type {
+        KeyType::Bool => BOOL_VALUES.contains(&text.to_lowercase().as_str()),
+        KeyType::Integer => text.parse::<i64>().is_ok(),
+        KeyType::Float => text.parse::<f64>().is_ok(),
+        KeyType::Size => is_size(&text),
+        KeyType::Text => !text.is_empty(),
+        KeyType::Addresses => {
+            return threshold::validate_ip_spec(&text).map_err(|e| format!("{}: {}", path, e))
+        }
+        KeyType::OneOf(values) => values.contains(&text.as_str()),
+        KeyType::List => unreachable!(),
+    };
+    if valid {
+        return Ok(());
+    }
+    let expected = match key_type {
+        KeyType::Bool => "yes or no".to_string(),
+        KeyType::Integer => "a whole number".to_string(),
+        KeyType::Float => "a number".to_string(),
+        KeyType::Size => "a size like 64mb".to_string(),
+        KeyType::OneOf(values) => format!("one of {}", values.join(", ")),
+        _ => "a value".to_string(),
+    };
+    Err(format!(
+        "Invalid value {} for {}, expected {}",
+        text, path, expected
+    ))
+}
+
+// Check a value against the type of its key, for keys with a known type
+pub fn validate(path: &str, value: &Value) -> Result<(), String> {
+    // Numeric segments index into lists, a single list item is checked as a
+    // plain value
+    let item = path
+        .rsplit('.')
+        .next()
+        .is_some_and(|segment| segment.parse::<usize>().is_ok());
+    let path: String = path
+        .split('.')
+        .filter(|segment| segment.parse::<usize>().is_err())
+        .collect::<Vec<_>>()
+        .join(".");
+    if path == HOME_NET_KEY
+        || HOME_NET_KEY.starts_with(&format!("{}.", path))
+        || path.starts_with(&format!("{}.", HOME_NET_KEY))
+    {
+        return Err(format!(
+            "{} would override HOME_NET, change HOME_NET with its own setting instead",
+            path
+        ));
+    }
+    match KEY_TYPES
+        .iter()
+        .find(|(pattern, _)| matches(pattern, &path))
+    {
+        Some((_, KeyType::List)) if item => check(KeyType::Text, &path, value),
+        Some((_, key_type)) => check(*key_type, &path, value),
+        None => match value {
+            Value::Array(items) if items.iter().all(|item| scalar_text(item).is_some()) => Ok(()),
+            Value::Array(_) => Err(format!("{} must be a list of values", path)),
+            _ => scalar_text(value)
+                .map(|_| ())
+                .ok_or_else(|| format!("{} must be a single value or a list", path)),
+        },
+    }
+}
@@ -0,0 +1,716 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use crate::backup::FileSnapshot;
+use crate::paths;
+
+// Highest category id Suricata accepts in a reputation categories file
+const MAX_IPREP_CATEGORY: u8 = 60;
+
+// Highest reputation score
+const MAX_IPREP_SCORE: u8 = 127;
+
+const IPREP_CATEGORIES_FILE: &str = "categories.txt";
+const IPREP_REPUTATION_FILE: &str = "reputation.list";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetType {
+    String,
+    Md5,
+    Sha256,
+    Ipv4,
+}
+
+impl DatasetType {
+    fn name(&self) -> &'static str {
+        match self {
+            DatasetType::String => "string",
+            DatasetType::Md5 => "md5",
+            DatasetType::Sha256 => "sha256",
+            DatasetType::Ipv4 => "ipv4",
+        }
+    }
+
+    // Turn an imported value into the form Suricata expects in the list
+    // file. Strings are stored base64 encoded.
+    fn normalize(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        let hex_of = |len: usize| {
+            if value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(value.to_ascii_lowercase())
+            } else {
+                Err(format!("Invalid {} hash: {}", self.name(), value))
+            }
+        };
+        match self {
+            DatasetType::String => {
+                if value.is_empty() {
+                    return Err("Empty string".to_string());
+                }
+                Ok(base64::engine::general_purpose::STANDARD.encode(value))
+            }
+            DatasetType::Md5 => hex_of(32),
+            DatasetType::Sha256 => hex_of(64),
+            DatasetType::Ipv4 => value
+                .parse::<Ipv4Addr>()
+                .map(|ip| ip.to_string())
+                .map_err(|_| format!("Invalid IPv4 address: {}", value)),
+        }
+    }
+
+    // Check a value as stored in a list file, already normalized
+    fn check_stored(&self, value: &str) -> Result<(), String> {
+        match self {
+            DatasetType::String => base64::engine::general_purpose::STANDARD
+                .decode(value)
+                .map(|_| ())
+                .map_err(|_| format!("Invalid base64 string: {}", value)),
+            _ => self.normalize(value).map(|_| ()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dataset {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub dataset_type: DatasetType,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetInfo {
+    #[serde(flatten)]
+    pub dataset: Dataset,
+    pub entries: usize,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    // One value per line, # starts a comment
+    Text,
+    // Comma separated, the value is taken from the given column
+    Csv,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportResult {
+    pub added: usize,
+    // Values already in the list
+    pub duplicates: usize,
+    // First few values that couldn't be imported, with the reason
+    pub invalid: Vec<String>,
+    pub invalid_count: usize,
+    pub total: usize,
+}
+
+impl ImportResult {
+    pub fn reject(&mut self, error: String) {
+        self.invalid_count += 1;
+        if self.invalid.len() < 20 {
+            self.invalid.push(error);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IprepCategory {
+    pub id: u8,
+    pub name: String,
+    pub description: String,
+    // Number of addresses in the reputation file with this category
+    #[serde(default)]
+    pub entries: usize,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.len() > 63
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "Invalid name {}, use up to 63 letters, digits, - and _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn manifest_path() -> Result<PathBuf, String> {
+    Ok(paths::datasets_dir()?.join("datasets.json"))
+}
+
+pub fn dataset_path(name: &str) -> Result<PathBuf, String> {
+    Ok(paths::datasets_dir()?.join(format!("{}.lst", name)))
+}
+
+fn load_datasets() -> Result<Vec<Dataset>, String> {
+    let path = manifest_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read datasets: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse datasets: {}", e))
+}
+
+fn save_datasets(datasets: &[Dataset]) -> Result<(), String> {
+    let dir = paths::datasets_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create datasets directory: {}", e))?;
+    let content = serde_json::to_string_pretty(datasets)
+        .map_err(|e| format!("Failed to serialize datasets: {}", e))?;
+    std::fs::write(manifest_path()?, content)
+        .map_err(|e| format!("Failed to write datasets: {}", e))?;
+    write_include(datasets)
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Quote a path for yaml. Single quoted scalars keep backslashes as is.
+fn yaml_path(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "''"))
+}
+
+// Regenerate the yaml include pointing Suricata at the managed datasets and
+// reputation files
+fn write_include(datasets: &[Dataset]) -> Result<(), String> {
+    let mut yaml = String::from(
+        "%YAML 1.1\n---\n\n# Generated by Meerkat Desktop, changes will be overwritten\n",
+    );
+
+    if !datasets.is_empty() {
+        yaml.push_str("\ndatasets:\n");
+        for dataset in datasets {
+            yaml.push_str(&format!(
+                "  {}:\n    type: {}\n    load: {}\n",
+                dataset.name,
+                dataset.dataset_type.name(),
+                yaml_path(&dataset_path(&dataset.name)?)
+            ));
+        }
+    }
+
+    let iprep_dir = paths::iprep_dir()?;
+    if iprep_dir.join(IPREP_CATEGORIES_FILE).exists() {
+        yaml.push_str(&format!(
+            "\nreputation-categories-file: {}\ndefault-reputation-path: {}\nreputation-files:\n  - {}\n",
+            yaml_path(&iprep_dir.join(IPREP_CATEGORIES_FILE)),
+            yaml_path(&iprep_dir),
+            IPREP_REPUTATION_FILE
+        ));
+    }
+
+    let path = paths::datasets_include()?;
+    std::fs::write(&path, yaml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Parse and check the content of a datasets manifest
+pub fn parse_manifest(content: &str) -> Result<Vec<Dataset>, String> {
+    let datasets: Vec<Dataset> =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse datasets: {}", e))?;
+    for dataset in &datasets {
+        validate_name(&dataset.name)?;
+    }
+    Ok(datasets)
+}
+
+// Check the content of a dataset's list file against its type
+pub fn validate_list(dataset: &Dataset, content: &str) -> Result<(), String> {
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        dataset
+            .dataset_type
+            .check_stored(line)
+            .map_err(|e| format!("Dataset {} line {}: {}", dataset.name, i + 1, e))?;
+    }
+    Ok(())
+}
+
+// Check the content of categories.txt, returning the category ids
+pub fn parse_iprep_categories(content: &str) -> Result<Vec<u8>, String> {
+    let mut ids = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(3, ',');
+        let id = parts
+            .next()
+            .and_then(|id| id.trim().parse::<u8>().ok())
+            .filter(|id| (1..=MAX_IPREP_CATEGORY).contains(id));
+        let name = parts.next().map(|name| name.trim());
+        match (id, name) {
+            (Some(id), Some(name)) if validate_name(name).is_ok() => ids.push(id),
+            _ => {
+                return Err(format!(
+                    "{} line {}: expected id,name,description with an id from 1 to {}",
+                    IPREP_CATEGORIES_FILE,
+                    i + 1,
+                    MAX_IPREP_CATEGORY
+                ))
+            }
+        }
+    }
+    Ok(ids)
+}
+
+// Check the content of reputation.list, lines of "address,category,score"
+// with a known category
+pub fn validate_reputation(content: &str, categories: &[u8]) -> Result<(), String> {
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |e: String| format!("{} line {}: {}", IPREP_REPUTATION_FILE, i + 1, e);
+        let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+        let [address, category, score] = parts.as_slice() else {
+            return Err(error("expected address,category,score".to_string()));
+        };
+        if address.starts_with('!') {
+            return Err(error(format!("Negated address {}", address)));
+        }
+        crate::threshold::validate_address(address).map_err(error)?;
+        if !category
+            .parse::<u8>()
+            .is_ok_and(|category| categories.contains(&category))
+        {
+            return Err(error(format!("Unknown category {}", category)));
+        }
+        if !score
+            .parse::<u8>()
+            .is_ok_and(|score| score <= MAX_IPREP_SCORE)
+        {
+            return Err(error(format!(
+                "Score {} is not between 0 and {}",
+                score, MAX_IPREP_SCORE
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Regenerate the yaml include from the saved datasets, e.g. after the files
+// were restored from a backup
+pub fn regenerate_include() -> Result<(), String> {
+    write_include(&load_datasets()?)
+}
+
+// Pull the values out of imported text
+fn import_values(content: &str, format: ImportFormat, column: usize) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match format {
+            ImportFormat::Text => Some(line.to_string()),
+            ImportFormat::Csv => line
+                .split(',')
+                .nth(column)
+                .map(|value| value.trim().trim_matches('"').to_string()),
+        })
+        .collect()
+}
+
+fn read_import(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+#[tauri::command]
+pub fn list_datasets() -> Result<Vec<DatasetInfo>, String> {
+    load_datasets()?
+        .into_iter()
+        .map(|dataset| {
+            let path = dataset_path(&dataset.name)?;
+            Ok(DatasetInfo {
+                entries: read_lines(&path).len(),
+                path: path.display().to_string(),
+                dataset,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn create_dataset(
+    name: String,
+    dataset_type: DatasetType,
+    description: Option<String>,
+) -> Result<String, String> {
+    validate_name(&name)?;
+    let mut datasets = load_datasets()?;
+    if datasets.iter().any(|dataset| dataset.name == name) {
+        return Err(format!("Dataset {} already exists", name));
+    }
+
+    datasets.push(Dataset {
+        name: name.clone(),
+        dataset_type,
+        description: description.unwrap_or_default(),
+    });
+    // Suricata refuses to start if a configured dataset file is missing
+    let path = dataset_path(&name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create datasets directory: {}", e))?;
+    }
+    if !path.exists() {
+        std::fs::write(&path, "").map_err(|e| format!("Failed to create dataset: {}", e))?;
+    }
+    save_datasets(&datasets)?;
+    Ok(format!("Created dataset {}", name))
+}
+
+// Dataset with the given name and type, created if it doesn't exist yet
+pub fn ensure_dataset(
+    name: &str,
+    dataset_type: DatasetType,
+    description: &str,
+) -> Result<Dataset, String> {
+    if let Some(dataset) = load_datasets()?
+        .into_iter()
+        .find(|dataset| dataset.name == name)
+    {
+        if dataset.dataset_type != dataset_type {
+            return Err(format!(
+                "Dataset {} exists with type {}, expected {}",
+                name,
+                dataset.dataset_type.name(),
+                dataset_type.name()
+            ));
+        }
+        return Ok(dataset);
+    }
+    create_dataset(
+        name.to_string(),
+        dataset_type,
+        Some(description.to_string()),
+    )?;
+    Ok(Dataset {
+        name: name.to_string(),
+        dataset_type,
+        description: description.to_string(),
+    })
+}
+
+// Number of entries in a dataset, 0 when it doesn't exist
+pub fn entry_count(name: &str) -> Result<usize, String> {
+    Ok(read_lines(&dataset_path(name)?).len())
+}
+
+// Save the manifest, the yaml include and the lists of the given datasets
+// before they are changed
+pub fn snapshot(names: &[&str]) -> Result<FileSnapshot, String> {
+    let mut snapshot = FileSnapshot::default();
+    snapshot.add(manifest_path()?)?;
+    snapshot.add(paths::datasets_include()?)?;
+    for name in names {
+        snapshot.add(dataset_path(name)?)?;
+    }
+    Ok(snapshot)
+}
+
+// Number of enabled rules in suricata.rules that use a dataset, either by
+// name in a dataset keyword or by its list file, like the threat intel hash
+// rules do. Suricata fails to load them once the dataset is gone.
+fn rules_using(name: &str) -> Result<usize, String> {
+    let path = dataset_path(name)?.display().to_string();
+    let list = path.replace('\\', "/");
+    let content =
+        std::fs::read_to_string(paths::rules_dir()?.join("suricata.rules")).unwrap_or_default();
+    let uses = |line: &str| {
+        line.match_indices("dataset:").any(|(i, keyword)| {
+            line[i + keyword.len()..]
+                .split(';')
+                .next()
+                .and_then(|args| args.split(',').nth(1))
+                .is_some_and(|dataset| dataset.trim() == name)
+        }) || line.contains(&path)
+            || line.contains(&list)
+    };
+    Ok(content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| uses(line))
+        .count())
+}
+
+#[tauri::command]
+pub fn delete_dataset(name: String) -> Result<String, String> {
+    let mut datasets = load_datasets()?;
+    let before = datasets.len();
+    datasets.retain(|dataset| dataset.name != name);
+    if datasets.len() == before {
+        return Err(format!("Dataset {} not found", name));
+    }
+    let rules = rules_using(&name)?;
+    if rules > 0 {
+        return Err(format!(
+            "Dataset {} is used by {} enabled rules, remove or disable them first",
+            name, rules
+        ));
+    }
+    save_datasets(&datasets)?;
+    let _ = std::fs::remove_file(dataset_path(&name)?);
+    Ok(format!("Deleted dataset {}", name))
+}
+
+// Import values from a text or CSV file into a dataset. Values are validated
+// for the dataset type; with replace set the current entries are dropped.
+#[tauri::command]
+pub fn import_dataset(
+    name: String,
+    path: String,
+    format: ImportFormat,
+    column: Option<usize>,
+    replace: Option<bool>,
+) -> Result<ImportResult, String> {
+    let dataset = load_datasets()?
+        .into_iter()
+        .find(|dataset| dataset.name == name)
+        .ok_or_else(|| format!("Dataset {} not found", name))?;
+    let content = read_import(&path)?;
+    add_to_dataset(
+        &dataset,
+        import_values(&content, format, column.unwrap_or(0)),
+        replace.unwrap_or(false),
+    )
+}
+
+// Add values to a dataset's list file, skipping invalid values and ones
+// already present
+pub fn add_to_dataset(
+    dataset: &Dataset,
+    values: Vec<String>,
+    replace: bool,
+) -> Result<ImportResult, String> {
+    let list_path = dataset_path(&dataset.name)?;
+    let mut entries: BTreeSet<String> = if replace {
+        BTreeSet::new()
+    } else {
+        read_lines(&list_path).into_iter().collect()
+    };
+
+    let mut result = ImportResult::default();
+    for value in values {
+        match dataset.dataset_type.normalize(&value) {
+            Ok(entry) => {
+                if entries.insert(entry) {
+                    result.added += 1;
+                } else {
+                    result.duplicates += 1;
+                }
+            }
+            Err(e) => result.reject(e),
+        }
+    }
+    result.total = entries.len();
+
+    let mut content = entries.into_iter().collect::<Vec<_>>().join("\n");
+    content.push('\n');
+    std::fs::write(&list_path, content)
+        .map_err(|e| format!("Failed to write dataset {}: {}", dataset.name, e))?;
+    Ok(result)
+}
+
+// Reputation categories from categories.txt, lines of "id,name,description"
+fn load_iprep_categories() -> Result<Vec<IprepCategory>, String> {
+    let dir = paths::iprep_dir()?;
+    let entries = read_lines(&dir.join(IPREP_REPUTATION_FILE));
+    let categories = read_lines(&dir.join(IPREP_CATEGORIES_FILE))
+        .into_iter()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ',');
+            let id = parts.next()?.trim().parse::<u8>().ok()?;
+            let name = parts.next()?.trim().to_string();
+            let description = parts.next().unwrap_or("").trim().to_string();
+            let prefix = format!(",{},", id);
+            Some(IprepCategory {
+                id,
+                name,
+                description,
+                entries: entries.iter().filter(|e| e.contains(&prefix)).count(),
+            })
+        })
+        .collect();
+    Ok(categories)
+}
+
+fn save_iprep_categories(categories: &[IprepCategory]) -> Result<(), String> {
+    let dir = paths::iprep_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create reputation directory: {}", e))?;
+    let mut content = String::new();
+    for category in categories {
+        content.push_str(&format!(
+            "{},{},{}\n",
+            category.id, category.name, category.description
+        ));
+    }
+    std::fs::write(dir.join(IPREP_CATEGORIES_FILE), content)
+        .map_err(|e| format!("Failed to write reputation categories: {}", e))?;
+
+    // Suricata needs the reputation file to exist once it is configured
+    let reputation = dir.join(IPREP_REPUTATION_FILE);
+    if !reputation.exists() {
+        std::fs::write(&reputation, "")
+            .map_err(|e| format!("Failed to create reputation file: {}", e))?;
+    }
+    write_include(&load_datasets()?)
+}
+
+#[tauri::command]
+pub fn list_iprep_categories() -> Result<Vec<IprepCategory>, String> {
+    load_iprep_categories()
+}
+
+#[tauri::command]
+pub fn create_iprep_category(
+    id: u8,
+    name: String,
+    description: Option<String>,
+) -> Result<String, String> {
+    if id == 0 || id > MAX_IPREP_CATEGORY {
+        return Err(format!(
+            "Reputation category ids must be between 1 and {}",
+            MAX_IPREP_CATEGORY
+        ));
+    }
+    validate_name(&name)?;
+    let description = description.unwrap_or_default();
+    if description.contains('\n') {
+        return Err("Category description must be a single line".to_string());
+    }
+
+    let mut categories = load_iprep_categories()?;
+    if categories
+        .iter()
+        .any(|category| category.id == id || category.name == name)
+    {
+        return Err(format!(
+            "Reputation category {} or id {} already exists",
+            name, id
+        ));
+    }
+    categories.push(IprepCategory {
+        id,
+        name: name.clone(),
+        description,
+        entries: 0,
+    });
+    categories.sort_by_key(|category| category.id);
+    save_iprep_categories(&categories)?;
+    Ok(format!("Created reputation category {}", name))
+}
+
+#[tauri::command]
+pub fn delete_iprep_category(name: String) -> Result<String, String> {
+    let mut categories = load_iprep_categories()?;
+    let category = categories
+        .iter()
+        .position(|category| category.name == name)
+        .map(|i| categories.remove(i))
+        .ok_or_else(|| format!("Reputation category {} not found", name))?;
+    save_iprep_categories(&categories)?;
+
+    // Drop the addresses in the removed category
+    let path = paths::iprep_dir()?.join(IPREP_REPUTATION_FILE);
+    let prefix = format!(",{},", category.id);
+    let mut content: String = read_lines(&path)
+        .into_iter()
+        .filter(|line| !line.contains(&prefix))
+        .collect::<Vec<_>>()
+        .join("\n");
+    content.push('\n');
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write reputation file: {}", e))?;
+    Ok(format!("Deleted reputation category {}", name))
+}
+
+// Import addresses or CIDRs into a reputation category with the given score.
+// With replace set the category's current addresses are dropped.
+#[tauri::command]
+pub fn import_iprep(
+    category: String,
+    path: String,
+    format: ImportFormat,
+    column: Option<usize>,
+    score: Option<u8>,
+    replace: Option<bool>,
+) -> Result<ImportResult, String> {
+    let category = load_iprep_categories()?
+        .into_iter()
+        .find(|c| c.name == category)
+        .ok_or_else(|| format!("Reputation category {} not found", category))?;
+    let score = score.unwrap_or(100);
+    if score > MAX_IPREP_SCORE {
+        return Err(format!(
+            "Reputation scores must be between 0 and {}",
+            MAX_IPREP_SCORE
+        ));
+    }
+    let content = read_import(&path)?;
+
+    let reputation_path = paths::iprep_dir()?.join(IPREP_REPUTATION_FILE);
+    let prefix = format!(",{},", category.id);
+    let mut lines = read_lines(&reputation_path);
+    if replace.unwrap_or(false) {
+        lines.retain(|line| !line.contains(&prefix));
+    }
+    let mut addresses: BTreeSet<String> = lines
+        .iter()
+        .filter(|line| line.contains(&prefix))
+        .filter_map(|line| line.split(',').next().map(|ip| ip.to_string()))
+        .collect();
+
+    let mut result = ImportResult::default();
+    for value in import_values(&content, format, column.unwrap_or(0)) {
+        if let Err(e) = crate::threshold::validate_address(&value) {
+            result.reject(e);
+            continue;
+        }
+        if value.starts_with('!') {
+            result.reject(format!("Negated addresses can't be imported: {}", value));
+            continue;
+        }
+        if addresses.insert(value.clone()) {
+            lines.push(format!("{},{},{}", value, category.id, score));
+            result.added += 1;
+        } else {
+            result.duplicates += 1;
+        }
+    }
+    result.total = addresses.len();
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    std::fs::write(&reputation_path, content)
+        .map_err(|e| format!("Failed to write reputation file: {}", e))?;
+    Ok(result)
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+use std::net::Ipv4Addr;
+
+use crate::config::overrides;
+use crate::config::{self, GeneratedConfig};
+use crate::settings;
+
+// Private address ranges, always suggested as part of HOME_NET
+const RFC1918_NETWORKS: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(10, 0, 0, 0), 8),
+    (Ipv4Addr::new(172, 16, 0, 0), 12),
+    (Ipv4Addr::new(192, 168, 0, 0), 16),
+];
+
+// Used when an adapter's subnet mask is unknown
+const DEFAULT_PREFIX_LEN: u8 = 24;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuggestedNetwork {
+    pub cidr: String,
+    // Adapter the network was found on, None for the RFC1918 defaults
+    pub interface: Option<String>,
+    // Already covered by an RFC1918 range, so not listed separately
+    pub covered: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HomeNetSuggestion {
+    pub networks: Vec<SuggestedNetwork>,
+    // Suggested HOME_NET, the interface networks outside the RFC1918 ranges
+    // followed by the ranges themselves
+    pub home_net: Vec<String>,
+    // HOME_NET currently in the settings
+    pub current: Vec<String>,
+}
+
+fn network(ip: Ipv4Addr, prefix_len: u8) -> Ipv4Addr {
+    let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+    Ipv4Addr::from(u32::from(ip) & mask)
+}
+
+fn contains(range: (Ipv4Addr, u8), ip: Ipv4Addr, prefix_len: u8) -> bool {
+    prefix_len >= range.1 && network(ip, range.1) == range.0
+}
+
+#[tauri::command]
+pub fn suggest_home_net() -> Result<HomeNetSuggestion, String> {
+    let mut networks: Vec<SuggestedNetwork> = Vec::new();
+    let mut home_net = Vec::new();
+
+    // The adapter list is best effort, the RFC1918 ranges are suggested even
+    // when it can't be read
+    let interfaces = crate::read_network_interfaces().unwrap_or_default();
+    for interface in &interfaces {
+        let Ok(ip) = interface.ip.parse::<Ipv4Addr>() else {
+            continue;
+        };
+        if ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() {
+            continue;
+        }
+        let prefix_len = interface.prefix_len.unwrap_or(DEFAULT_PREFIX_LEN);
+        let cidr = format!("{}/{}", network(ip, prefix_len), prefix_len);
+        if networks.iter().any(|existing| existing.cidr == cidr) {
+            continue;
+        }
+        let covered = RFC1918_NETWORKS
+            .iter()
+            .any(|range| contains(*range, ip, prefix_len));
+        if !covered {
+            home_net.push(cidr.clone());
+        }
+        networks.push(SuggestedNetwork {
+            cidr,
+            interface: Some(interface.name.clone()),
+            covered,
+        });
+    }
+
+    for (ip, prefix_len) in RFC1918_NETWORKS {
+        let cidr = format!("{}/{}", ip, prefix_len);
+        home_net.push(cidr.clone());
+        networks.push(SuggestedNetwork {
+            cidr,
+            interface: None,
+            covered: false,
+        });
+    }
+
+    Ok(HomeNetSuggestion {
+        networks,
+        home_net,
+        current: settings::load()?.suricata_config.home_net,
+    })
+}
+
+// Save HOME_NET and regenerate suricata.yaml with it. Takes effect the next
+// time Suricata is started.
+#[tauri::command]
+pub fn set_home_net(home_net: Vec<String>) -> Result<GeneratedConfig, String> {
+    let mut settings = settings::load()?;
+    settings.suricata_config.home_net = home_net
+        .iter()
+        .map(|address| address.trim().to_string())
+        .filter(|address| !address.is_empty())
+        .collect();
+    settings.suricata_config.validate()?;
+    config::build(&settings.suricata_config, &overrides::load()?)?;
+    settings::save(&settings)?;
+    config::generate(&settings.suricata_config)
+}
@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::settings::Settings;
+
+// Shared HTTP client used for every outbound download, rebuilt whenever the
+// proxy or CA settings change
+pub struct HttpClient {
+    client: Mutex<reqwest::Client>,
+    // Why the saved proxy or CA settings couldn't be applied at startup,
+    // cleared once settings are saved that work
+    error: Mutex<Option<String>>,
+}
+
+impl HttpClient {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client: Mutex::new(client),
+            error: Mutex::new(None),
+        }
+    }
+
+    // A fallback client used because the saved settings failed to apply
+    pub fn fallback(error: String) -> Self {
+        Self {
+            client: Mutex::new(reqwest::Client::new()),
+            error: Mutex::new(Some(error)),
+        }
+    }
+
+    pub fn get(&self) -> reqwest::Client {
+        // reqwest::Client is reference counted, so cloning is cheap
+        match self.client.lock() {
+            Ok(client) => client.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub fn replace(&self, client: reqwest::Client) {
+        if let Ok(mut guard) = self.client.lock() {
+            *guard = client;
+        }
+        if let Ok(mut error) = self.error.lock() {
+            *error = None;
+        }
+    }
+}
+
+// The error applying the saved HTTP settings, if the app fell back to a
+// default client at startup
+#[tauri::command]
+pub fn get_http_settings_error(http_client: State<'_, HttpClient>) -> Option<String> {
+    http_client
+        .error
+        .lock()
+        .ok()
+        .and_then(|error| error.clone())
+}
+
+pub fn build_client(settings: &Settings) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(concat!("Meerkat-Desktop/", env!("CARGO_PKG_VERSION")));
+
+    let proxy_settings = &settings.proxy;
+    if !proxy_settings.url.trim().is_empty() {
+        let mut proxy = reqwest::Proxy::all(proxy_settings.url.trim())
+            .map_err(|e| format!("Invalid proxy URL: {}", e))?;
+        if !proxy_settings.username.is_empty() {
+            proxy = proxy.basic_auth(&proxy_settings.username, &proxy_settings.password);
+        }
+        if !proxy_settings.no_proxy.is_empty() {
+            let no_proxy = reqwest::NoProxy::from_string(&proxy_settings.no_proxy.join(","));
+            proxy = proxy.no_proxy(no_proxy);
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    for path in &settings.ca_certificates {
+        let data = std::fs::read(path)
+            .map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))?;
+        let certificates = if data.starts_with(b"-----BEGIN") {
+            reqwest::Certificate::from_pem_bundle(&data)
+        } else {
+            reqwest::Certificate::from_der(&data).map(|cert| vec![cert])
+        }
+        .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
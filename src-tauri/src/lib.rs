@@ -1,22 +1,38 @@
-use flate2::read::GzDecoder;
 use futures_util::StreamExt;
 use regex::Regex;
 use std::path::Path;
 use std::process::Command;
 use std::sync::Arc;
 use std::sync::Mutex;
-use tar::Archive;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as TokioCommand;
 
+mod backup;
+mod config;
+mod datasets;
+mod home_net;
+mod http;
+mod paths;
+mod profiles;
+mod rules;
+mod settings;
+mod system_check;
+mod threshold;
+
+use http::HttpClient;
+use rules::index::RuleIndexState;
+use rules::schedule::UpdateLock;
+
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
 // Struct to hold the Suricata process handle
 struct SuricataProcess {
     handle: Option<tokio::process::Child>,
+    // Interface Suricata was last started on, used to restart it
+    interface: Option<String>,
 }
 
 // Struct to manage eve.json tailing
@@ -41,8 +57,27 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-fn get_network_interfaces() -> Result<Vec<String>, String> {
+// An Ethernet adapter with its IPv4 address
+struct NetworkInterface {
+    name: String,
+    ip: String,
+    // From the subnet mask, None when ipconfig didn't list one
+    prefix_len: Option<u8>,
+    guid: String,
+}
+
+// Prefix length of a subnet mask like 255.255.255.0
+fn mask_prefix_len(mask: &str) -> Option<u8> {
+    let mask: std::net::Ipv4Addr = mask.trim().parse().ok()?;
+    let bits = u32::from(mask);
+    // Only contiguous masks are valid
+    if bits.leading_ones() + bits.trailing_zeros() != 32 {
+        return None;
+    }
+    Some(bits.leading_ones() as u8)
+}
+
+fn read_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
     #[cfg(target_os = "windows")]
     {
         use std::str;
@@ -100,6 +135,7 @@ fn get_network_interfaces() -> Result<Vec<String>, String> {
         let mut interfaces = Vec::new();
         let mut current_adapter: Option<String> = None;
         let mut current_ip: Option<String> = None;
+        let mut current_mask: Option<String> = None;
 
         // Parse the ipconfig output line by line
         for line in ipconfig_str.lines() {
@@ -108,13 +144,19 @@ fn get_network_interfaces() -> Result<Vec<String>, String> {
             // Check for Ethernet adapter lines
             if trimmed.starts_with("Ethernet adapter") && trimmed.ends_with(":") {
                 // Save previous adapter if it has an IP
+                let mask = current_mask.take();
                 if let (Some(adapter), Some(ip)) = (current_adapter.take(), current_ip.take()) {
                     // Try to find the GUID for this adapter
                     let guid = adapter_guids
                         .get(&adapter)
                         .cloned()
                         .unwrap_or_else(|| "GUID not found".to_string());
-                    interfaces.push(format!("{} - {} - {}", adapter, ip, guid));
+                    interfaces.push(NetworkInterface {
+                        name: adapter,
+                        ip,
+                        prefix_len: mask.as_deref().and_then(mask_prefix_len),
+                        guid,
+                    });
                 }
 
                 // Extract adapter name
@@ -131,6 +173,12 @@ fn get_network_interfaces() -> Result<Vec<String>, String> {
                     current_ip = Some(ip.to_string());
                 }
             }
+            // Subnet mask of the adapter's address
+            else if trimmed.starts_with("Subnet Mask") {
+                if let Some(mask_part) = trimmed.split(':').nth(1) {
+                    current_mask = Some(mask_part.trim().to_string());
+                }
+            }
             // Check for Autoconfiguration IPv4 Address (fallback)
             else if current_ip.is_none() && trimmed.starts_with("Autoconfiguration IPv4 Address")
             {
@@ -147,15 +195,15 @@ fn get_network_interfaces() -> Result<Vec<String>, String> {
                 .get(&adapter)
                 .cloned()
                 .unwrap_or_else(|| "GUID not found".to_string());
-            interfaces.push(format!("{} - {} - {}", adapter, ip, guid));
+            interfaces.push(NetworkInterface {
+                name: adapter,
+                ip,
+                prefix_len: current_mask.as_deref().and_then(mask_prefix_len),
+                guid,
+            });
         }
 
-        // If no interfaces found, return an error
-        if interfaces.is_empty() {
-            Ok(vec!["No Ethernet adapters found".to_string()])
-        } else {
-            Ok(interfaces)
-        }
+        Ok(interfaces)
     }
 
     #[cfg(not(target_os = "windows"))]
@@ -165,7 +213,25 @@ fn get_network_interfaces() -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-async fn install_suricata(app: AppHandle) -> Result<String, String> {
+fn get_network_interfaces() -> Result<Vec<String>, String> {
+    let interfaces = read_network_interfaces()?;
+
+    // If no interfaces found, return an error
+    if interfaces.is_empty() {
+        Ok(vec!["No Ethernet adapters found".to_string()])
+    } else {
+        Ok(interfaces
+            .iter()
+            .map(|interface| format!("{} - {} - {}", interface.name, interface.ip, interface.guid))
+            .collect())
+    }
+}
+
+#[tauri::command]
+async fn install_suricata(
+    app: AppHandle,
+    http_client: State<'_, HttpClient>,
+) -> Result<String, String> {
     // URL for Suricata installer
     let url = "https://www.openinfosecfoundation.org/download/windows/Suricata-7.0.10-1-64bit.msi";
 
@@ -175,7 +241,12 @@ async fn install_suricata(app: AppHandle) -> Result<String, String> {
     let installer_path = downloads_dir.join("Suricata-7.0.10-1-64bit.msi");
 
     // Download the installer
-    let response = reqwest::get(url).await.map_err(|e| e.to_string())?;
+    let response = http_client
+        .get()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to download: HTTP {}", response.status()));
@@ -238,7 +309,10 @@ async fn install_suricata(app: AppHandle) -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn install_npcap(app: AppHandle) -> Result<String, String> {
+async fn install_npcap(
+    app: AppHandle,
+    http_client: State<'_, HttpClient>,
+) -> Result<String, String> {
     // URL for NPCap installer
     let url = "https://npcap.com/dist/npcap-1.82.exe";
 
@@ -248,7 +322,12 @@ async fn install_npcap(app: AppHandle) -> Result<String, String> {
     let installer_path = downloads_dir.join("npcap-1.82.exe");
 
     // Download the installer
-    let response = reqwest::get(url).await.map_err(|e| e.to_string())?;
+    let response = http_client
+        .get()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to download: HTTP {}", response.status()));
@@ -312,7 +391,10 @@ async fn install_npcap(app: AppHandle) -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn install_evebox(app: AppHandle) -> Result<String, String> {
+async fn install_evebox(
+    app: AppHandle,
+    http_client: State<'_, HttpClient>,
+) -> Result<String, String> {
     // URL for EveBox download
     let url = "https://evebox.org/files/release/latest/evebox-0.20.5-windows-x64.zip";
 
@@ -332,7 +414,12 @@ async fn install_evebox(app: AppHandle) -> Result<String, String> {
     let temp_extract_dir = std::path::Path::new(&evebox_base_dir).join("temp-extract");
 
     // Download the zip file
-    let response = reqwest::get(url).await.map_err(|e| e.to_string())?;
+    let response = http_client
+        .get()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to download: HTTP {}", response.status()));
@@ -503,10 +590,18 @@ fn check_suricata_status() -> Result<bool, String> {
 async fn start_suricata_with_output(
     app: AppHandle,
     suricata_process: State<'_, Mutex<SuricataProcess>>,
-    selected_interface: String,
+    selected_interface: Option<String>,
+    profile: Option<String>,
 ) -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
+        // Switch to the requested profile first, its interface is used when
+        // none is selected
+        let selected_interface = match &profile {
+            Some(profile) => profiles::prepare_start(profile, selected_interface)?,
+            None => selected_interface.ok_or_else(|| "No interface selected".to_string())?,
+        };
+
         // Stop any existing process
         {
             let child = if let Ok(mut process_guard) = suricata_process.lock() {
@@ -571,12 +666,32 @@ async fn start_suricata_with_output(
             .map_err(|e| format!("Failed to create threshold.conf: {}", e))?;
         }
 
+        // Command socket used to reload rules without a restart. A socket
+        // left behind by a previous run would stop Suricata creating it.
+        let command_socket = paths::command_socket()?;
+        let _ = std::fs::remove_file(&command_socket);
+
+        // Suricata runs with the configuration generated from the settings
+        let suricata_config = config::generate(&settings::load()?.suricata_config)?;
+
         // Build the command
-        let suricata_command = format!(
-            r"cd '{}'; .\suricata.exe -v -i '{}' -c .\suricata.yaml -l '{}' -S '{}' --set threshold-file='{}'",
-            suricata_dir, interface_guid, log_dir, rules_path, threshold_path
+        let mut suricata_command = format!(
+            r"cd '{}'; .\suricata.exe -v -i '{}' -c '{}' -l '{}' -S '{}' --set threshold-file='{}' --set unix-command.enabled=yes --set unix-command.filename='{}'",
+            suricata_dir,
+            interface_guid,
+            suricata_config.path,
+            log_dir,
+            rules_path,
+            threshold_path,
+            command_socket.display()
         );
 
+        // Datasets and IP reputation files managed by the app
+        let datasets_include = paths::datasets_include()?;
+        if datasets_include.exists() {
+            suricata_command.push_str(&format!(" --include '{}'", datasets_include.display()));
+        }
+
         // Emit the command to the output terminal
         let _ = app.emit(
             "suricata-output",
@@ -623,6 +738,7 @@ async fn start_suricata_with_output(
         {
             if let Ok(mut process_guard) = suricata_process.lock() {
                 process_guard.handle = Some(child);
+                process_guard.interface = Some(selected_interface.clone());
             }
         }
         if let Some(stdout) = stdout {
@@ -672,6 +788,40 @@ async fn start_suricata_with_output(
     }
 }
 
+// Restart Suricata on the interface it was last started on so it picks up
+// the current rules. Returns false if Suricata is not running.
+async fn restart_suricata(app: &AppHandle) -> Result<bool, String> {
+    let interface = {
+        let suricata_process = app.state::<Mutex<SuricataProcess>>();
+        let guard = suricata_process
+            .lock()
+            .map_err(|_| "Suricata process lock poisoned".to_string())?;
+        if guard.handle.is_none() {
+            return Ok(false);
+        }
+        guard.interface.clone()
+    };
+    let Some(interface) = interface else {
+        return Ok(false);
+    };
+
+    stop_suricata_with_output(app.clone(), app.state()).await?;
+    start_suricata_with_output(app.clone(), app.state(), Some(interface), None).await?;
+    Ok(true)
+}
+
+// Whether a Suricata process started by the app is still running
+fn suricata_running(app: &AppHandle) -> bool {
+    let suricata_process = app.state::<Mutex<SuricataProcess>>();
+    let Ok(mut guard) = suricata_process.lock() else {
+        return false;
+    };
+    guard
+        .handle
+        .as_mut()
+        .is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+}
+
 #[tauri::command]
 async fn stop_suricata_with_output(
     app: AppHandle,
@@ -947,223 +1097,6 @@ async fn stop_eve_json_tail(eve_json_tailer: State<'_, EveJsonTailer>) -> Result
     Ok("Stopped tailing eve.json".to_string())
 }
 
-#[tauri::command]
-async fn update_rules(app: AppHandle) -> Result<String, String> {
-    // Define rule sources
-    struct RuleSource {
-        url: &'static str,
-        is_archive: bool,
-    }
-
-    let sources = vec![
-        RuleSource {
-            url: "https://rules.emergingthreats.net/open/suricata-7.0/emerging.rules.tar.gz",
-            is_archive: true,
-        },
-        RuleSource {
-            url: "https://openinfosecfoundation.org/rules/trafficid/trafficid.rules",
-            is_archive: false,
-        },
-        RuleSource {
-            url: "https://rules.pawpatrules.fr/suricata/paw-patrules.tar.gz",
-            is_archive: true,
-        },
-    ];
-
-    // Get user's home directory for rules
-    let rules_dir = std::env::var("USERPROFILE")
-        .map(|home| format!(r"{}\.meerkat-desktop\rules", home))
-        .unwrap_or_else(|_| r"C:\suricata\rules".to_string());
-
-    // Create rules directory if it doesn't exist
-    std::fs::create_dir_all(&rules_dir)
-        .map_err(|e| format!("Failed to create rules directory: {}", e))?;
-
-    // Start with empty rules
-    let mut all_rules = String::new();
-    let mut total_rule_files = 0;
-
-    // Process each source
-    for (index, source) in sources.iter().enumerate() {
-        let _ = app.emit(
-            "rules-update-progress",
-            serde_json::json!({
-                "type": "info",
-                "message": format!("Downloading from: {}", source.url),
-                "url": source.url,
-                "current_source": index + 1,
-                "total_sources": sources.len()
-            }),
-        );
-
-        let response = reqwest::get(source.url).await.map_err(|e| e.to_string())?;
-
-        if !response.status().is_success() {
-            return Err(format!(
-                "Failed to download from {}: HTTP {}",
-                source.url,
-                response.status()
-            ));
-        }
-
-        // Get content length for progress tracking
-        let total_size = response.content_length().unwrap_or(0);
-
-        if source.is_archive {
-            // Handle tar.gz files
-            let temp_path = format!("{}/temp_{}.tar.gz", rules_dir, index);
-            let mut file = tokio::fs::File::create(&temp_path)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Stream download with progress
-            let mut stream = response.bytes_stream();
-            let mut downloaded = 0u64;
-
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| e.to_string())?;
-                tokio::io::AsyncWriteExt::write_all(&mut file, &chunk)
-                    .await
-                    .map_err(|e| e.to_string())?;
-                downloaded += chunk.len() as u64;
-
-                // Emit progress
-                if total_size > 0 {
-                    let progress = (downloaded as f64 / total_size as f64 * 100.0) as u32;
-                    let _ = app.emit(
-                        "rules-update-progress",
-                        serde_json::json!({
-                            "type": "download",
-                            "progress": progress,
-                            "downloaded": downloaded,
-                            "total": total_size,
-                            "url": source.url
-                        }),
-                    );
-                }
-            }
-
-            // Ensure file is flushed
-            tokio::io::AsyncWriteExt::flush(&mut file)
-                .await
-                .map_err(|e| e.to_string())?;
-            drop(file);
-
-            let _ = app.emit(
-                "rules-update-progress",
-                serde_json::json!({
-                    "type": "info",
-                    "message": format!("Extracting rules from: {}", source.url)
-                }),
-            );
-
-            // Extract the tar.gz file
-            let tar_gz = std::fs::File::open(&temp_path).map_err(|e| e.to_string())?;
-            let tar = GzDecoder::new(tar_gz);
-            let mut archive = Archive::new(tar);
-
-            // Extract to temporary directory
-            let temp_extract_dir = format!("{}/temp_extract_{}", rules_dir, index);
-            std::fs::create_dir_all(&temp_extract_dir).map_err(|e| e.to_string())?;
-
-            archive
-                .unpack(&temp_extract_dir)
-                .map_err(|e| format!("Failed to extract archive: {}", e))?;
-
-            // Process extracted files
-            let mut rule_count = 0;
-            let mut support_file_count = 0;
-
-            fn process_extracted_files(
-                src_dir: &Path,
-                rules_dir: &Path,
-                all_rules: &mut String,
-                rule_count: &mut i32,
-                support_file_count: &mut i32,
-            ) -> std::io::Result<()> {
-                if src_dir.is_dir() {
-                    for entry in std::fs::read_dir(src_dir)? {
-                        let entry = entry?;
-                        let path = entry.path();
-                        if path.is_dir() {
-                            process_extracted_files(
-                                &path,
-                                rules_dir,
-                                all_rules,
-                                rule_count,
-                                support_file_count,
-                            )?;
-                        } else if path.extension().and_then(|s| s.to_str()) == Some("rules") {
-                            // Read and concatenate .rules files
-                            let content = std::fs::read_to_string(&path)?;
-                            all_rules.push_str(&content);
-                            all_rules.push('\n');
-                            *rule_count += 1;
-                        } else if let Some(file_name) = path.file_name() {
-                            // Copy non-.rules files to the rules directory
-                            let dest_path = rules_dir.join(file_name);
-                            std::fs::copy(&path, &dest_path)?;
-                            *support_file_count += 1;
-                        }
-                    }
-                }
-                Ok(())
-            }
-
-            process_extracted_files(
-                Path::new(&temp_extract_dir),
-                Path::new(&rules_dir),
-                &mut all_rules,
-                &mut rule_count,
-                &mut support_file_count,
-            )
-            .map_err(|e| format!("Failed to process extracted files: {}", e))?;
-
-            total_rule_files += rule_count;
-
-            if support_file_count > 0 {
-                let _ = app.emit("rules-update-progress", serde_json::json!({
-                    "type": "info",
-                    "message": format!("Copied {} support files from: {}", support_file_count, source.url)
-                }));
-            }
-
-            // Clean up temporary files
-            let _ = std::fs::remove_file(&temp_path);
-            let _ = std::fs::remove_dir_all(&temp_extract_dir);
-        } else {
-            // Handle direct .rules files
-            let content = response.text().await.map_err(|e| e.to_string())?;
-            all_rules.push_str(&content);
-            all_rules.push('\n');
-            total_rule_files += 1;
-
-            let _ = app.emit(
-                "rules-update-progress",
-                serde_json::json!({
-                    "type": "info",
-                    "message": format!("Downloaded rules from: {}", source.url)
-                }),
-            );
-        }
-    }
-
-    // Write concatenated rules to suricata.rules
-    let suricata_rules_path = format!("{}/suricata.rules", rules_dir);
-    std::fs::write(&suricata_rules_path, &all_rules)
-        .map_err(|e| format!("Failed to write suricata.rules: {}", e))?;
-
-    let _ = app.emit("rules-update-progress", serde_json::json!({
-        "type": "complete",
-        "message": format!("Rules updated successfully! Processed {} rule files from {} sources.", total_rule_files, sources.len())
-    }));
-
-    Ok(format!(
-        "Rules updated successfully! {} rule files concatenated into {}",
-        total_rule_files, suricata_rules_path
-    ))
-}
-
 #[tauri::command]
 async fn start_evebox_with_output(
     app: AppHandle,
@@ -1453,15 +1386,36 @@ fn show_dependency_dialog(app: AppHandle, missing_deps: Vec<String>) -> Result<(
     Ok(())
 }
 
+// Build the shared HTTP client from saved settings. If the saved proxy or CA
+// settings can't be applied, fall back to a default client so the app still
+// starts and the settings can be corrected. The error is kept for the UI.
+fn build_http_client() -> HttpClient {
+    match settings::load().and_then(|settings| http::build_client(&settings)) {
+        Ok(client) => HttpClient::new(client),
+        Err(e) => HttpClient::fallback(format!("Failed to apply HTTP settings: {}", e)),
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(Mutex::new(SuricataProcess { handle: None }))
+        .manage(build_http_client())
+        .manage(RuleIndexState::default())
+        .manage(UpdateLock::default())
+        .manage(Mutex::new(SuricataProcess {
+            handle: None,
+            interface: None,
+        }))
         .manage(Mutex::new(EveBoxProcess { handle: None }))
         .manage(EveJsonTailer {
             is_running: Arc::new(Mutex::new(false)),
         })
+        .setup(|app| {
+            // Run scheduled rule updates in the background
+            tauri::async_runtime::spawn(rules::schedule::run_scheduler(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_network_interfaces,
@@ -1477,10 +1431,70 @@ pub fn run() {
             open_evebox_url,
             start_eve_json_tail,
             stop_eve_json_tail,
-            update_rules,
+            http::get_http_settings_error,
+            rules::update_rules,
+            rules::schedule::get_update_status,
             check_npcap_installed,
             check_suricata_installed,
-            show_dependency_dialog
+            show_dependency_dialog,
+            settings::get_settings,
+            settings::save_settings,
+            system_check::system_check,
+            threshold::list_thresholds,
+            threshold::add_threshold,
+            threshold::update_threshold,
+            threshold::remove_threshold,
+            threshold::suppress_alert,
+            threshold::undo_suppression,
+            config::generate_suricata_config,
+            config::get_suricata_config,
+            config::get_suricata_config_diff,
+            config::overrides::get_config_value,
+            config::overrides::set_config_value,
+            config::overrides::remove_config_override,
+            config::overrides::list_config_overrides,
+            config::overrides::test_suricata_config,
+            home_net::suggest_home_net,
+            home_net::set_home_net,
+            profiles::list_profiles,
+            profiles::create_profile,
+            profiles::clone_profile,
+            profiles::switch_profile,
+            profiles::delete_profile,
+            datasets::list_datasets,
+            datasets::create_dataset,
+            datasets::delete_dataset,
+            datasets::import_dataset,
+            datasets::list_iprep_categories,
+            datasets::create_iprep_category,
+            datasets::delete_iprep_category,
+            datasets::import_iprep,
+            backup::export_config,
+            backup::import_config,
+            rules::sources::list_rule_sources,
+            rules::sources::enable_rule_source,
+            rules::sources::disable_rule_source,
+            rules::sources::add_rule_source,
+            rules::sources::remove_rule_source,
+            rules::index::search_rules,
+            rules::index::get_rule,
+            rules::stats::get_ruleset_stats,
+            rules::ioc::import_iocs,
+            rules::ioc::get_ioc_summary,
+            rules::diff::get_last_rule_diff,
+            rules::versions::list_rule_versions,
+            rules::versions::rollback_rules,
+            rules::reload::reload_rules,
+            rules::local::list_local_rule_files,
+            rules::local::create_local_rule_file,
+            rules::local::delete_local_rule_file,
+            rules::local::get_local_rules,
+            rules::local::add_local_rule,
+            rules::local::update_local_rule,
+            rules::local::delete_local_rule,
+            rules::policy::get_rule_policy,
+            rules::policy::save_rule_policy,
+            rules::policy::preview_rule_policy
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
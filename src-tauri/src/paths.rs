@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+pub const SURICATA_DIR: &str = r"C:\Program Files\Suricata";
+pub const NPCAP_DRIVER: &str = r"C:\Windows\System32\drivers\npcap.sys";
+
+// Base directory for everything Meerkat Desktop stores, %USERPROFILE%\.meerkat-desktop
+pub fn data_dir() -> Result<PathBuf, String> {
+    std::env::var("USERPROFILE")
+        .map(|home| PathBuf::from(home).join(".meerkat-desktop"))
+        .map_err(|_| "Could not find user profile directory".to_string())
+}
+
+pub fn settings_path() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("settings.json"))
+}
+
+pub fn log_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("log"))
+}
+
+pub fn rules_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("rules"))
+}
+
+pub fn threshold_path() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("threshold.conf"))
+}
+
+// User maintained rule files that are merged into every rule update
+pub fn local_rules_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("local-rules"))
+}
+
+pub fn evebox_exe() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("evebox").join("bin").join("evebox.exe"))
+}
+
+pub fn suricata_exe() -> PathBuf {
+    PathBuf::from(SURICATA_DIR).join("suricata.exe")
+}
+
+// Unix socket Suricata is started with for commands like reload-rules
+pub fn command_socket() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("suricata-command.socket"))
+}
+
+// Managed dataset lists and IP reputation files
+pub fn datasets_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("datasets"))
+}
+
+pub fn iprep_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("iprep"))
+}
+
+// Generated yaml passed to Suricata with --include, configuring the managed
+// datasets and IP reputation files
+pub fn datasets_include() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("datasets.yaml"))
+}
+
+// Generated Suricata configuration
+pub fn config_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("config"))
+}
+
+pub fn suricata_config() -> Result<PathBuf, String> {
+    Ok(config_dir()?.join("suricata.yaml"))
+}
+
+// Saved sensor profiles, one directory per profile
+pub fn profiles_dir() -> Result<PathBuf, String> {
+    Ok(data_dir()?.join("profiles"))
+}
@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use crate::config::overrides::{self, Overrides};
+use crate::config::{self, GeneratedConfig};
+use crate::paths;
+use crate::settings;
+
+const PROFILE_FILE: &str = "profile.json";
+const THRESHOLD_FILE: &str = "threshold.conf";
+const ACTIVE_FILE: &str = "active";
+
+// A named sensor setup. The active profile's settings live in the usual
+// places (settings, config overrides, threshold.conf) and are saved back into
+// the profile when switching away from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    // Interface Suricata is started on with this profile
+    pub interface: Option<String>,
+    pub home_net: Vec<String>,
+    pub config_overrides: Overrides,
+    // Names of the rule sources enabled with this profile
+    pub enabled_sources: Vec<String>,
+    pub created_at: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            interface: None,
+            home_net: Vec::new(),
+            config_overrides: Overrides::new(),
+            enabled_sources: Vec::new(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInfo {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub active: bool,
+    // Number of entries in the profile's threshold.conf
+    pub thresholds: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSwitch {
+    pub profile: String,
+    pub config: GeneratedConfig,
+    // The enabled rule sources changed, rules need updating to match
+    pub rules_update_needed: bool,
+    pub message: String,
+}
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.trim() == name
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid profile name {}, use up to 64 letters, digits, spaces, - and _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn profile_dir(name: &str) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    Ok(paths::profiles_dir()?.join(name))
+}
+
+fn active_name() -> Result<Option<String>, String> {
+    let path = paths::profiles_dir()?.join(ACTIVE_FILE);
+    Ok(std::fs::read_to_string(path)
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| profile_dir(name).is_ok_and(|dir| dir.join(PROFILE_FILE).exists())))
+}
+
+fn set_active(name: &str) -> Result<(), String> {
+    let dir = paths::profiles_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create profiles directory: {}", e))?;
+    std::fs::write(dir.join(ACTIVE_FILE), name)
+        .map_err(|e| format!("Failed to write active profile: {}", e))
+}
+
+fn load(name: &str) -> Result<(Profile, String), String> {
+    let dir = profile_dir(name)?;
+    let content = std::fs::read_to_string(dir.join(PROFILE_FILE))
+        .map_err(|_| format!("Profile {} not found", name))?;
+    let profile: Profile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse profile {}: {}", name, e))?;
+    let threshold = std::fs::read_to_string(dir.join(THRESHOLD_FILE)).unwrap_or_default();
+    Ok((profile, threshold))
+}
+
+fn save(profile: &Profile, threshold: &str) -> Result<(), String> {
+    let dir = profile_dir(&profile.name)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create profile {}: {}", profile.name, e))?;
+    let content = serde_json::to_string_pretty(profile)
+        .map_err(|e| format!("Failed to serialize profile: {}", e))?;
+    std::fs::write(dir.join(PROFILE_FILE), content)
+        .map_err(|e| format!("Failed to write profile {}: {}", profile.name, e))?;
+    std::fs::write(dir.join(THRESHOLD_FILE), threshold)
+        .map_err(|e| format!("Failed to write profile {}: {}", profile.name, e))
+}
+
+// The current settings as a profile, along with the threshold.conf contents
+fn capture(name: &str, interface: Option<String>) -> Result<(Profile, String), String> {
+    let settings = settings::load()?;
+    let threshold = std::fs::read_to_string(paths::threshold_path()?).unwrap_or_default();
+    let profile = Profile {
+        name: name.to_string(),
+        interface,
+        home_net: settings.suricata_config.home_net,
+        config_overrides: overrides::load()?,
+        enabled_sources: settings
+            .rule_sources
+            .iter()
+            .filter(|source| source.enabled)
+            .map(|source| source.name.clone())
+            .collect(),
+        ..Profile::default()
+    };
+    Ok((profile, threshold))
+}
+
+// Save the current settings into the active profile, keeping its interface
+fn save_active() -> Result<(), String> {
+    let Some(name) = active_name()? else {
+        return Ok(());
+    };
+    let (existing, _) = load(&name)?;
+    let (profile, threshold) = capture(&name, existing.interface)?;
+    save(
+        &Profile {
+            created_at: existing.created_at,
+            ..profile
+        },
+        &threshold,
+    )
+}
+
+// Make a profile's settings the current ones and regenerate suricata.yaml
+fn apply(profile: &Profile, threshold: &str) -> Result<(GeneratedConfig, bool), String> {
+    let mut settings = settings::load()?;
+    let enabled_before: BTreeSet<String> = settings
+        .rule_sources
+        .iter()
+        .filter(|source| source.enabled)
+        .map(|source| source.name.clone())
+        .collect();
+    for source in &mut settings.rule_sources {
+        source.enabled = profile.enabled_sources.contains(&source.name);
+    }
+    let enabled_after: BTreeSet<String> = settings
+        .rule_sources
+        .iter()
+        .filter(|source| source.enabled)
+        .map(|source| source.name.clone())
+        .collect();
+    if !profile.home_net.is_empty() {
+        settings.suricata_config.home_net = profile.home_net.clone();
+    }
+    settings.suricata_config.validate()?;
+    // Check the config builds before anything is written, so a failure leaves
+    // the current profile's settings in place
+    config::build(&settings.suricata_config, &profile.config_overrides)?;
+
+    settings::save(&settings)?;
+    overrides::save(&profile.config_overrides)?;
+    std::fs::write(paths::threshold_path()?, threshold)
+        .map_err(|e| format!("Failed to write threshold.conf: {}", e))?;
+    let config = config::generate(&settings.suricata_config)?;
+    Ok((config, enabled_before != enabled_after))
+}
+
+fn switch(name: &str) -> Result<ProfileSwitch, String> {
+    let (profile, threshold) = load(name)?;
+    if active_name()?.as_deref() != Some(name) {
+        save_active()?;
+    }
+    let (config, rules_update_needed) = apply(&profile, &threshold)?;
+    set_active(name)?;
+
+    let mut message = format!("Switched to profile {}", name);
+    if rules_update_needed {
+        message.push_str(", update the rules to apply its rule sources");
+    }
+    Ok(ProfileSwitch {
+        profile: name.to_string(),
+        config,
+        rules_update_needed,
+        message,
+    })
+}
+
+// Switch to a profile before starting Suricata and work out the interface to
+// start on. A selected interface is remembered in the profile.
+pub fn prepare_start(name: &str, selected_interface: Option<String>) -> Result<String, String> {
+    if active_name()?.as_deref() != Some(name) {
+        switch(name)?;
+    }
+    let (mut profile, threshold) = load(name)?;
+    match selected_interface {
+        Some(interface) => {
+            if profile.interface.as_ref() != Some(&interface) {
+                profile.interface = Some(interface.clone());
+                save(&profile, &threshold)?;
+            }
+            Ok(interface)
+        }
+        None => profile
+            .interface
+            .ok_or_else(|| format!("Profile {} has no interface, select one", name)),
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    let active = active_name()?;
+    let Ok(entries) = std::fs::read_dir(paths::profiles_dir()?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut profiles = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Some(name) = entry.file_name().to_str().map(|name| name.to_string()) else {
+            continue;
+        };
+        if !entry.path().join(PROFILE_FILE).exists() {
+            continue;
+        }
+        let is_active = active.as_deref() == Some(name.as_str());
+        let (mut profile, mut threshold) = load(&name)?;
+        // The active profile's settings are the current ones
+        if is_active {
+            let (current, current_threshold) = capture(&name, profile.interface.clone())?;
+            profile = Profile {
+                created_at: profile.created_at,
+                ..current
+            };
+            threshold = current_threshold;
+        }
+        profiles.push(ProfileInfo {
+            profile,
+            active: is_active,
+            thresholds: threshold
+                .lines()
+                .filter(|line| {
+                    let line = line.trim();
+                    !line.is_empty() && !line.starts_with('#')
+                })
+                .count(),
+        });
+    }
+    profiles.sort_by(|a, b| a.profile.name.cmp(&b.profile.name));
+    Ok(profiles)
+}
+
+// Create a profile from the current settings
+#[tauri::command]
+pub fn create_profile(name: String, interface: Option<String>) -> Result<String, String> {
+    if profile_dir(&name)?.exists() {
+        return Err(format!("Profile {} already exists", name));
+    }
+    let (profile, threshold) = capture(&name, interface)?;
+    save(&profile, &threshold)?;
+    // The first profile becomes the active one, it matches the current settings
+    if active_name()?.is_none() {
+        set_active(&name)?;
+    }
+    Ok(format!("Created profile {}", name))
+}
+
+#[tauri::command]
+pub fn clone_profile(source: String, name: String) -> Result<String, String> {
+    if profile_dir(&name)?.exists() {
+        return Err(format!("Profile {} already exists", name));
+    }
+    let (existing, existing_threshold) = load(&source)?;
+    let (profile, threshold) = if active_name()?.as_deref() == Some(source.as_str()) {
+        capture(&name, existing.interface)?
+    } else {
+        (existing, existing_threshold)
+    };
+    save(
+        &Profile {
+            name: name.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            ..profile
+        },
+        &threshold,
+    )?;
+    Ok(format!("Cloned profile {} to {}", source, name))
+}
+
+#[tauri::command]
+pub fn switch_profile(name: String) -> Result<ProfileSwitch, String> {
+    switch(&name)
+}
+
+#[tauri::command]
+pub fn delete_profile(name: String) -> Result<String, String> {
+    let dir = profile_dir(&name)?;
+    if !dir.join(PROFILE_FILE).exists() {
+        return Err(format!("Profile {} not found", name));
+    }
+    if active_name()?.as_deref() == Some(name.as_str()) {
+        return Err(format!(
+            "Profile {} is active, switch to another profile first",
+            name
+        ));
+    }
+    std::fs::remove_dir_all(&dir)
+        .map_err(|e| format!("Failed to delete profile {}: {}", name, e))?;
+    Ok(format!("Deleted profile {}", name))
+}
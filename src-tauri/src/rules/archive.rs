@@ -0,0 +1,191 @@
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::{Component, Path};
+use tar::Archive;
+
+use super::sources::RuleSourceType;
+
+// Limits on what a rule archive may unpack to, so a corrupt or hostile
+// archive can't exhaust memory
+const MAX_ENTRIES: usize = 10_000;
+const MAX_ENTRY_SIZE: u64 = 128 * 1024 * 1024;
+pub const MAX_TOTAL_SIZE: u64 = 512 * 1024 * 1024;
+
+// Extensions of the non-rule files kept from archives, such as
+// classification.config, sid-msg.map and dataset lists. Everything else is
+// ignored.
+const SUPPORT_EXTENSIONS: &[&str] = &["config", "map", "lst", "csv", "dataset"];
+
+pub enum EntryKind {
+    Rules,
+    Support,
+}
+
+// A file read from an archive
+pub struct ArchiveEntry {
+    // Relative path inside the archive, using / as the separator
+    pub path: String,
+    pub kind: EntryKind,
+    pub data: Vec<u8>,
+}
+
+impl ArchiveEntry {
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+// Check an entry path only walks down into the archive and return it in
+// normalized form
+pub fn normalize_path(path: &Path) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                let part = part.to_str().ok_or_else(|| {
+                    format!("Archive entry has an invalid name: {}", path.display())
+                })?;
+                parts.push(part);
+            }
+            Component::CurDir => {}
+            _ => {
+                return Err(format!(
+                    "Archive entry escapes the archive: {}",
+                    path.display()
+                ))
+            }
+        }
+    }
+    if parts.is_empty() {
+        return Err("Archive entry has an empty name".to_string());
+    }
+    Ok(parts.join("/"))
+}
+
+fn entry_kind(path: &str) -> Option<EntryKind> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    if file_name.starts_with('.') {
+        return None;
+    }
+    let extension = file_name.rsplit_once('.')?.1.to_ascii_lowercase();
+    if extension == "rules" {
+        Some(EntryKind::Rules)
+    } else if SUPPORT_EXTENSIONS.contains(&extension.as_str()) {
+        Some(EntryKind::Support)
+    } else {
+        None
+    }
+}
+
+// Tracks the limits while an archive is read
+struct Budget {
+    entries: usize,
+    total: u64,
+}
+
+impl Budget {
+    fn new() -> Self {
+        Self {
+            entries: 0,
+            total: 0,
+        }
+    }
+
+    fn entry(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            return Err(format!("Archive has more than {} entries", MAX_ENTRIES));
+        }
+        Ok(())
+    }
+
+    // Read an entry, never trusting its declared size
+    fn read(&mut self, path: &str, reader: impl Read) -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        reader
+            .take(MAX_ENTRY_SIZE + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {} from archive: {}", path, e))?;
+        if data.len() as u64 > MAX_ENTRY_SIZE {
+            return Err(format!(
+                "Archive entry {} is larger than {} MB",
+                path,
+                MAX_ENTRY_SIZE / 1024 / 1024
+            ));
+        }
+        self.total += data.len() as u64;
+        if self.total > MAX_TOTAL_SIZE {
+            return Err(format!(
+                "Archive unpacks to more than {} MB",
+                MAX_TOTAL_SIZE / 1024 / 1024
+            ));
+        }
+        Ok(data)
+    }
+}
+
+fn read_tar_gz(data: &[u8]) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = Archive::new(GzDecoder::new(data));
+    let mut budget = Budget::new();
+    let mut entries = Vec::new();
+
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read archive: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read archive: {}", e))?;
+        budget.entry()?;
+
+        // Links, devices and directories are never extracted
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .map_err(|e| format!("Failed to read archive entry name: {}", e))?;
+        let path = normalize_path(&path)?;
+        let Some(kind) = entry_kind(&path) else {
+            continue;
+        };
+        let data = budget.read(&path, entry)?;
+        entries.push(ArchiveEntry { path, kind, data });
+    }
+    Ok(entries)
+}
+
+fn read_zip(data: &[u8]) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+        .map_err(|e| format!("Failed to open zip archive: {}", e))?;
+    let mut budget = Budget::new();
+    let mut entries = Vec::new();
+
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read zip archive: {}", e))?;
+        budget.entry()?;
+
+        if !file.is_file() || file.is_symlink() {
+            continue;
+        }
+        let path = normalize_path(Path::new(file.name()))?;
+        let Some(kind) = entry_kind(&path) else {
+            continue;
+        };
+        let data = budget.read(&path, file)?;
+        entries.push(ArchiveEntry { path, kind, data });
+    }
+    Ok(entries)
+}
+
+// Read the rule and support files from a downloaded archive in memory.
+// Nothing is written to disk, entries are returned sorted by path.
+pub fn read_entries(source_type: RuleSourceType, data: &[u8]) -> Result<Vec<ArchiveEntry>, String> {
+    let mut entries = match source_type {
+        RuleSourceType::TarGz => read_tar_gz(data)?,
+        RuleSourceType::Zip => read_zip(data)?,
+        RuleSourceType::Rules => Vec::new(),
+    };
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
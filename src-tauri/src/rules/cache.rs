@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+// Validators and checksum for the last good download of a rule source, stored
+// next to the cached content as <source>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheMetadata {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub sha256: String,
+    pub fetched_at: String,
+}
+
+pub struct CachedSource {
+    pub metadata: CacheMetadata,
+    pub data: Vec<u8>,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+pub fn cache_dir(rules_dir: &Path) -> PathBuf {
+    rules_dir.join("cache")
+}
+
+// Source names like "et/open" aren't valid file names. The result is always
+// a single path component: dots are only kept inside the name, so it can't be
+// . or .. and Windows can't strip them from the end.
+pub fn file_stem(source_name: &str) -> String {
+    let chars: Vec<char> = source_name.chars().collect();
+    let stem: String = chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let inner_dot = c == '.' && i > 0 && i + 1 < chars.len();
+            if c.is_ascii_alphanumeric() || c == '-' || inner_dot {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "_".to_string()
+    } else {
+        stem
+    }
+}
+
+fn data_path(rules_dir: &Path, source_name: &str) -> PathBuf {
+    cache_dir(rules_dir).join(format!("{}.data", file_stem(source_name)))
+}
+
+fn metadata_path(rules_dir: &Path, source_name: &str) -> PathBuf {
+    cache_dir(rules_dir).join(format!("{}.json", file_stem(source_name)))
+}
+
+// Load the cached copy of a source. Returns None if there is no cache, it
+// was downloaded from a different URL, or the content no longer matches the
+// recorded checksum.
+pub fn load(rules_dir: &Path, source_name: &str, url: &str) -> Option<CachedSource> {
+    let metadata = std::fs::read_to_string(metadata_path(rules_dir, source_name)).ok()?;
+    let metadata: CacheMetadata = serde_json::from_str(&metadata).ok()?;
+    if metadata.url != url {
+        return None;
+    }
+    let data = std::fs::read(data_path(rules_dir, source_name)).ok()?;
+    if sha256_hex(&data) != metadata.sha256 {
+        return None;
+    }
+    Some(CachedSource { metadata, data })
+}
+
+pub fn store(
+    rules_dir: &Path,
+    source_name: &str,
+    url: &str,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    data: &[u8],
+) -> Result<CacheMetadata, String> {
+    std::fs::create_dir_all(cache_dir(rules_dir))
+        .map_err(|e| format!("Failed to create rule cache directory: {}", e))?;
+
+    let metadata = CacheMetadata {
+        url: url.to_string(),
+        etag,
+        last_modified,
+        sha256: sha256_hex(data),
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    // Write the data before the metadata so a partial write is caught by the
+    // checksum on the next load
+    std::fs::write(data_path(rules_dir, source_name), data)
+        .map_err(|e| format!("Failed to write rule cache: {}", e))?;
+    let json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize rule cache metadata: {}", e))?;
+    std::fs::write(metadata_path(rules_dir, source_name), json)
+        .map_err(|e| format!("Failed to write rule cache metadata: {}", e))?;
+
+    Ok(metadata)
+}
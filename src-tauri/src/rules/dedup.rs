@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::local::LOCAL_SOURCE;
+use super::parser::{self, Rule};
+
+// How to pick the rule to keep when several sources provide the same gid:sid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateStrategy {
+    // Keep the highest rev, using source priority to break ties
+    #[default]
+    HighestRev,
+    // Keep the rule from the highest priority source, using rev to break ties
+    SourcePriority,
+}
+
+// Only this many duplicates are listed individually in the report
+const MAX_REPORTED: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateRule {
+    pub source: Option<String>,
+    pub rev: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateSid {
+    pub gid: u64,
+    pub sid: u64,
+    pub kept: DuplicateRule,
+    pub dropped: Vec<DuplicateRule>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DuplicateStats {
+    // Number of gid:sid pairs that had more than one rule
+    pub duplicate_sids: usize,
+    // Number of rules removed
+    pub removed: usize,
+    pub duplicates: Vec<DuplicateSid>,
+}
+
+struct Candidate {
+    line: usize,
+    rev: u64,
+    enabled: bool,
+    priority: i32,
+    // Local rules are kept whatever the strategy, rev and priorities
+    local: bool,
+    // Position of the source in the update, earlier wins a full tie
+    order: usize,
+    source: Option<String>,
+}
+
+// Remove duplicate rules from a combined rules file. Lines that are not
+// rules are kept as is.
+pub fn dedup_text(
+    text: &str,
+    strategy: DuplicateStrategy,
+    priorities: &HashMap<String, i32>,
+) -> (String, DuplicateStats) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut candidates: HashMap<(u64, u64), Vec<Candidate>> = HashMap::new();
+    let mut source_order: HashMap<String, usize> = HashMap::new();
+    let mut current_source: Option<String> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix(parser::SOURCE_MARKER.trim_end()) {
+            let name = name.trim().to_string();
+            let order = source_order.len();
+            source_order.entry(name.clone()).or_insert(order);
+            current_source = Some(name);
+            continue;
+        }
+        let Ok(rule) = Rule::parse(trimmed) else {
+            continue;
+        };
+        let priority = current_source
+            .as_ref()
+            .and_then(|source| priorities.get(source))
+            .copied()
+            .unwrap_or(0);
+        let order = current_source
+            .as_ref()
+            .and_then(|source| source_order.get(source))
+            .copied()
+            .unwrap_or(usize::MAX);
+        candidates
+            .entry((rule.gid, rule.sid))
+            .or_default()
+            .push(Candidate {
+                line: i,
+                rev: rule.rev,
+                enabled: rule.enabled,
+                priority,
+                local: current_source.as_deref() == Some(LOCAL_SOURCE),
+                order,
+                source: current_source.clone(),
+            });
+    }
+
+    let mut stats = DuplicateStats::default();
+    let mut removed_lines = HashSet::new();
+
+    let mut duplicate_keys: Vec<(u64, u64)> = candidates
+        .iter()
+        .filter(|(_, c)| c.len() > 1)
+        .map(|(key, _)| *key)
+        .collect();
+    duplicate_keys.sort();
+
+    for key in duplicate_keys {
+        let Some(mut group) = candidates.remove(&key) else {
+            continue;
+        };
+        // Best candidate first
+        group.sort_by(|a, b| {
+            let by_local = b.local.cmp(&a.local);
+            let by_rev = b.rev.cmp(&a.rev);
+            let by_priority = b.priority.cmp(&a.priority);
+            let primary = match strategy {
+                DuplicateStrategy::HighestRev => by_rev.then(by_priority),
+                DuplicateStrategy::SourcePriority => by_priority.then(by_rev),
+            };
+            by_local
+                .then(primary)
+                .then(b.enabled.cmp(&a.enabled))
+                .then(a.order.cmp(&b.order))
+                .then(a.line.cmp(&b.line))
+        });
+
+        let mut group = group.into_iter();
+        let Some(kept) = group.next() else {
+            continue;
+        };
+        let dropped: Vec<Candidate> = group.collect();
+
+        stats.duplicate_sids += 1;
+        stats.removed += dropped.len();
+        removed_lines.extend(dropped.iter().map(|c| c.line));
+
+        if stats.duplicates.len() < MAX_REPORTED {
+            stats.duplicates.push(DuplicateSid {
+                gid: key.0,
+                sid: key.1,
+                kept: DuplicateRule {
+                    source: kept.source,
+                    rev: kept.rev,
+                },
+                dropped: dropped
+                    .into_iter()
+                    .map(|c| DuplicateRule {
+                        source: c.source,
+                        rev: c.rev,
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    if removed_lines.is_empty() {
+        return (text.to_string(), stats);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in lines.iter().enumerate() {
+        if removed_lines.contains(&i) {
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    (out, stats)
+}
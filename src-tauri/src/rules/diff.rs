@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::parser::Rule;
+use crate::paths;
+
+// Number of diff reports kept in the reports directory
+const MAX_REPORTS: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleChange {
+    pub gid: u64,
+    pub sid: u64,
+    pub rev: u64,
+    // Only set for modified rules
+    pub previous_rev: Option<u64>,
+    pub msg: String,
+    pub source: Option<String>,
+}
+
+impl RuleChange {
+    fn new(rule: &Rule, previous_rev: Option<u64>) -> Self {
+        Self {
+            gid: rule.gid,
+            sid: rule.sid,
+            rev: rule.rev,
+            previous_rev,
+            msg: rule.msg.clone(),
+            source: rule.source.clone(),
+        }
+    }
+}
+
+// Changes between the previous and the new ruleset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleDiff {
+    pub timestamp: String,
+    pub added: Vec<RuleChange>,
+    pub removed: Vec<RuleChange>,
+    // Rules whose rev changed
+    pub modified: Vec<RuleChange>,
+    // Rules present in both that were enabled or disabled
+    pub enabled: Vec<RuleChange>,
+    pub disabled: Vec<RuleChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleDiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub enabled: usize,
+    pub disabled: usize,
+    pub report_path: Option<String>,
+}
+
+impl RuleDiff {
+    pub fn summary(&self, report_path: Option<&Path>) -> RuleDiffSummary {
+        RuleDiffSummary {
+            added: self.added.len(),
+            removed: self.removed.len(),
+            modified: self.modified.len(),
+            enabled: self.enabled.len(),
+            disabled: self.disabled.len(),
+            report_path: report_path.map(|path| path.display().to_string()),
+        }
+    }
+}
+
+pub fn diff(old: &[Rule], new: &[Rule]) -> RuleDiff {
+    let old_by_sid: HashMap<(u64, u64), &Rule> = old
+        .iter()
+        .map(|rule| ((rule.gid, rule.sid), rule))
+        .collect();
+    let new_by_sid: HashMap<(u64, u64), &Rule> = new
+        .iter()
+        .map(|rule| ((rule.gid, rule.sid), rule))
+        .collect();
+
+    let mut diff = RuleDiff {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    for rule in new {
+        match old_by_sid.get(&(rule.gid, rule.sid)) {
+            None => diff.added.push(RuleChange::new(rule, None)),
+            Some(previous) => {
+                if previous.rev != rule.rev {
+                    diff.modified
+                        .push(RuleChange::new(rule, Some(previous.rev)));
+                }
+                if !previous.enabled && rule.enabled {
+                    diff.enabled.push(RuleChange::new(rule, None));
+                } else if previous.enabled && !rule.enabled {
+                    diff.disabled.push(RuleChange::new(rule, None));
+                }
+            }
+        }
+    }
+
+    for rule in old {
+        if !new_by_sid.contains_key(&(rule.gid, rule.sid)) {
+            diff.removed.push(RuleChange::new(rule, None));
+        }
+    }
+
+    diff
+}
+
+fn reports_dir() -> Result<PathBuf, String> {
+    Ok(paths::rules_dir()?.join("reports"))
+}
+
+// Save a diff as a JSON report, removing the oldest reports beyond
+// MAX_REPORTS
+pub fn save(diff: &RuleDiff) -> Result<PathBuf, String> {
+    let dir = reports_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create reports directory: {}", e))?;
+
+    let path = dir.join(format!(
+        "diff-{}.json",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    let json = serde_json::to_string_pretty(diff)
+        .map_err(|e| format!("Failed to serialize rule diff: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write rule diff: {}", e))?;
+
+    let reports = list_reports(&dir);
+    if reports.len() > MAX_REPORTS {
+        for old in &reports[..reports.len() - MAX_REPORTS] {
+            let _ = std::fs::remove_file(old);
+        }
+    }
+
+    Ok(path)
+}
+
+// Diff reports, oldest first. The timestamped names sort chronologically.
+fn list_reports(dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("diff-") && name.ends_with(".json"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    reports
+}
+
+// The diff from the most recent rule update
+#[tauri::command]
+pub fn get_last_rule_diff() -> Result<Option<RuleDiff>, String> {
+    let Some(path) = list_reports(&reports_dir()?).pop() else {
+        return Ok(None);
+    };
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+use super::parser::{self, Rule};
+use crate::paths;
+
+// Parsed rules from suricata.rules, indexed by gid and sid
+pub struct RuleIndex {
+    rules: Vec<Rule>,
+    by_sid: HashMap<(u64, u64), usize>,
+}
+
+impl RuleIndex {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let by_sid = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| ((rule.gid, rule.sid), i))
+            .collect();
+        Self { rules, by_sid }
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(Self::new(parser::parse_rules(&text, None)))
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn get(&self, gid: u64, sid: u64) -> Option<&Rule> {
+        self.by_sid.get(&(gid, sid)).map(|i| &self.rules[*i])
+    }
+}
+
+// Lazily loaded rule index shared between commands. Cleared whenever the
+// rules on disk change so the next query reloads them.
+#[derive(Default)]
+pub struct RuleIndexState {
+    index: Mutex<Option<Arc<RuleIndex>>>,
+}
+
+impl RuleIndexState {
+    pub fn get(&self) -> Result<Arc<RuleIndex>, String> {
+        let mut guard = self
+            .index
+            .lock()
+            .map_err(|_| "Rule index lock poisoned".to_string())?;
+        if let Some(index) = guard.as_ref() {
+            return Ok(index.clone());
+        }
+        let path = paths::rules_dir()?.join("suricata.rules");
+        let index = Arc::new(RuleIndex::load(&path)?);
+        *guard = Some(index.clone());
+        Ok(index)
+    }
+
+    pub fn invalidate(&self) {
+        if let Ok(mut guard) = self.index.lock() {
+            *guard = None;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RuleQuery {
+    pub sid: Option<u64>,
+    // Case insensitive substring match on msg
+    pub msg: Option<String>,
+    pub classtype: Option<String>,
+    pub metadata_key: Option<String>,
+    // Only used together with metadata_key
+    pub metadata_value: Option<String>,
+    pub source: Option<String>,
+    pub enabled: Option<bool>,
+    pub offset: usize,
+    // Defaults to 100 when not set
+    pub limit: Option<usize>,
+}
+
+impl RuleQuery {
+    fn matches(&self, rule: &Rule, msg: Option<&str>) -> bool {
+        if let Some(sid) = self.sid {
+            if rule.sid != sid {
+                return false;
+            }
+        }
+        if let Some(msg) = msg {
+            if !rule.msg.to_lowercase().contains(msg) {
+                return false;
+            }
+        }
+        if let Some(classtype) = &self.classtype {
+            if rule.classtype.as_deref() != Some(classtype.as_str()) {
+                return false;
+            }
+        }
+        if let Some(key) = &self.metadata_key {
+            let mut values = rule.metadata_values(key).peekable();
+            match &self.metadata_value {
+                Some(value) => {
+                    if !values.any(|v| v.eq_ignore_ascii_case(value)) {
+                        return false;
+                    }
+                }
+                None => {
+                    if values.peek().is_none() {
+                        return false;
+                    }
+                }
+            }
+        }
+        if let Some(source) = &self.source {
+            if rule.source.as_deref() != Some(source.as_str()) {
+                return false;
+            }
+        }
+        if let Some(enabled) = self.enabled {
+            if rule.enabled != enabled {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The subset of a rule shown in search results
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSummary {
+    pub gid: u64,
+    pub sid: u64,
+    pub rev: u64,
+    pub msg: String,
+    pub action: String,
+    pub protocol: String,
+    pub classtype: Option<String>,
+    pub enabled: bool,
+    pub source: Option<String>,
+}
+
+impl From<&Rule> for RuleSummary {
+    fn from(rule: &Rule) -> Self {
+        Self {
+            gid: rule.gid,
+            sid: rule.sid,
+            rev: rule.rev,
+            msg: rule.msg.clone(),
+            action: rule.action.clone(),
+            protocol: rule.protocol.clone(),
+            classtype: rule.classtype.clone(),
+            enabled: rule.enabled,
+            source: rule.source.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSearchResult {
+    // Number of rules matching the query, before offset and limit
+    pub total: usize,
+    pub rules: Vec<RuleSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleDetails {
+    pub rule: Rule,
+    // The rule as it appears in the rules file
+    pub text: String,
+}
+
+#[tauri::command]
+pub async fn search_rules(
+    rule_index: State<'_, RuleIndexState>,
+    query: RuleQuery,
+) -> Result<RuleSearchResult, String> {
+    let index = rule_index.get()?;
+    let msg = query.msg.as_ref().map(|msg| msg.to_lowercase());
+    let limit = query.limit.unwrap_or(100);
+
+    let mut total = 0;
+    let mut rules = Vec::new();
+    for rule in index.rules() {
+        if !query.matches(rule, msg.as_deref()) {
+            continue;
+        }
+        if total >= query.offset && rules.len() < limit {
+            rules.push(RuleSummary::from(rule));
+        }
+        total += 1;
+    }
+
+    Ok(RuleSearchResult { total, rules })
+}
+
+#[tauri::command]
+pub async fn get_rule(
+    rule_index: State<'_, RuleIndexState>,
+    sid: u64,
+    gid: Option<u64>,
+) -> Result<RuleDetails, String> {
+    let index = rule_index.get()?;
+    let rule = index
+        .get(gid.unwrap_or(1), sid)
+        .ok_or_else(|| format!("Rule {} not found", sid))?;
+    Ok(RuleDetails {
+        text: rule.to_string(),
+        rule: rule.clone(),
+    })
+}
@@ -0,0 +1,576 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use tauri::{AppHandle, Manager, State};
+
+use super::index::RuleIndexState;
+use super::parser;
+use super::policy::{self, Policies};
+use super::reload::{self, ReloadResult};
+use super::schedule::UpdateLock;
+use super::{dedup, local, source_priorities, validate};
+use crate::datasets::{self, DatasetType, ImportResult};
+use crate::paths;
+use crate::settings;
+
+// Source name the generated threat intel rules are merged under
+pub const IOC_SOURCE: &str = "threat-intel";
+
+const RULE_FILE: &str = "threat-intel.rules";
+
+// SIDs of the generated rules, outside the ranges used by public rule sets
+// and the default local range
+const IOC_SID_BASE: u64 = 9_900_000;
+
+// Application protocols file hashes are checked on
+const FILE_PROTOCOLS: &[&str] = &["http", "smtp", "smb", "ftp-data", "nfs"];
+
+// Only this many skipped indicators are listed in the report
+const MAX_SKIPPED: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IocType {
+    Domain,
+    Ipv4,
+    Md5,
+    Sha256,
+    Ja3,
+    Ja4,
+}
+
+const IOC_TYPES: &[IocType] = &[
+    IocType::Domain,
+    IocType::Ipv4,
+    IocType::Md5,
+    IocType::Sha256,
+    IocType::Ja3,
+    IocType::Ja4,
+];
+
+impl IocType {
+    // Type names used by common CTI exports
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "domain" | "domain-name" | "hostname" | "fqdn" | "host" => Some(IocType::Domain),
+            "ip" | "ipv4" | "ip-dst" | "ip-src" | "ipv4-addr" => Some(IocType::Ipv4),
+            "md5" | "filemd5" | "file-md5" => Some(IocType::Md5),
+            "sha256" | "sha-256" | "filesha256" | "file-sha256" => Some(IocType::Sha256),
+            "ja3" | "ja3-fingerprint-md5" | "ja3_hash" => Some(IocType::Ja3),
+            "ja4" | "ja4-fingerprint" => Some(IocType::Ja4),
+            _ => None,
+        }
+    }
+
+    // Guess the type of an indicator without a type column. JA3 hashes look
+    // like MD5 hashes and need an explicit type.
+    fn infer(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let hex = !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit());
+        if hex && value.len() == 32 {
+            Some(IocType::Md5)
+        } else if hex && value.len() == 64 {
+            Some(IocType::Sha256)
+        } else if IocType::Ipv4.normalize(value).is_ok() {
+            Some(IocType::Ipv4)
+        } else if IocType::Ja4.normalize(value).is_ok() {
+            Some(IocType::Ja4)
+        } else if IocType::Domain.normalize(value).is_ok() {
+            Some(IocType::Domain)
+        } else {
+            None
+        }
+    }
+
+    fn dataset(&self) -> (&'static str, DatasetType) {
+        match self {
+            IocType::Domain => ("ti-domains", DatasetType::String),
+            IocType::Ipv4 => ("ti-ipv4", DatasetType::Ipv4),
+            IocType::Md5 => ("ti-md5", DatasetType::Md5),
+            IocType::Sha256 => ("ti-sha256", DatasetType::Sha256),
+            IocType::Ja3 => ("ti-ja3", DatasetType::String),
+            IocType::Ja4 => ("ti-ja4", DatasetType::String),
+        }
+    }
+
+    // Clean up an indicator, undoing the usual defanging like evil[.]com
+    fn normalize(&self, value: &str) -> Result<String, String> {
+        let value = value
+            .trim()
+            .trim_matches('"')
+            .replace("[.]", ".")
+            .replace("(.)", ".");
+        let hex = |len: usize, name: &str| {
+            if value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(value.to_ascii_lowercase())
+            } else {
+                Err(format!("Invalid {}: {}", name, value))
+            }
+        };
+        match self {
+            IocType::Domain => {
+                let domain = value.trim_end_matches('.').to_lowercase();
+                let valid = domain.contains('.')
+                    && domain.len() <= 253
+                    && domain.split('.').all(|label| {
+                        !label.is_empty()
+                            && label.len() <= 63
+                            && label
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    });
+                if !valid {
+                    return Err(format!("Invalid domain: {}", value));
+                }
+                Ok(domain)
+            }
+            IocType::Ipv4 => {
+                let ip = value.strip_suffix("/32").unwrap_or(&value);
+                if ip.contains('/') {
+                    return Err(format!(
+                        "Address ranges can't be used in datasets, use IP reputation: {}",
+                        value
+                    ));
+                }
+                ip.parse::<Ipv4Addr>()
+                    .map(|ip| ip.to_string())
+                    .map_err(|_| format!("Invalid IPv4 address: {}", value))
+            }
+            IocType::Md5 => hex(32, "MD5 hash"),
+            IocType::Sha256 => hex(64, "SHA-256 hash"),
+            IocType::Ja3 => hex(32, "JA3 fingerprint"),
+            IocType::Ja4 => {
+                let ja4 = value.to_lowercase();
+                let parts: Vec<&str> = ja4.split('_').collect();
+                let valid = parts.len() == 3
+                    && parts[0].len() == 10
+                    && parts
+                        .iter()
+                        .all(|part| part.chars().all(|c| c.is_ascii_alphanumeric()));
+                if !valid {
+                    return Err(format!("Invalid JA4 fingerprint: {}", value));
+                }
+                Ok(ja4)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IocFormat {
+    // Rows of type and value, with an optional header naming the columns.
+    // Rows with a single column have their type guessed.
+    Csv,
+    // STIX 2 bundle with indicators or observables
+    Stix,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IocImportReport {
+    pub by_type: BTreeMap<IocType, ImportResult>,
+    // Indicators of unsupported types or that couldn't be read
+    pub skipped: usize,
+    pub skipped_samples: Vec<String>,
+    // Number of generated rules now active
+    pub rules: usize,
+    pub message: String,
+    pub reload: Option<ReloadResult>,
+}
+
+impl IocImportReport {
+    fn skip(&mut self, reason: String) {
+        self.skipped += 1;
+        if self.skipped_samples.len() < MAX_SKIPPED {
+            self.skipped_samples.push(reason);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IocSummary {
+    #[serde(rename = "type")]
+    pub ioc_type: IocType,
+    pub dataset: String,
+    pub entries: usize,
+}
+
+// Indicators read from an import, the type is None when it isn't supported
+type Indicator = (Option<IocType>, String);
+
+fn split_csv(line: &str) -> Vec<String> {
+    line.split(',')
+        .map(|field| field.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+fn parse_csv(content: &str) -> Vec<Indicator> {
+    let mut lines = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    // Use the header to find the type and value columns when there is one
+    let mut columns = None;
+    if let Some(header) = lines.peek() {
+        let header: Vec<String> = split_csv(header)
+            .into_iter()
+            .map(|field| field.to_lowercase())
+            .collect();
+        let find = |names: &[&str]| {
+            header
+                .iter()
+                .position(|field| names.contains(&field.as_str()))
+        };
+        let type_column = find(&["type", "ioc_type", "indicator_type"]);
+        let value_column = find(&["value", "ioc", "indicator"]);
+        if let (Some(type_column), Some(value_column)) = (type_column, value_column) {
+            columns = Some((type_column, value_column));
+            lines.next();
+        }
+    }
+
+    lines
+        .filter_map(|line| {
+            let fields = split_csv(line);
+            match columns {
+                Some((type_column, value_column)) => {
+                    let value = fields.get(value_column)?.clone();
+                    let ioc_type = fields
+                        .get(type_column)
+                        .and_then(|name| IocType::from_name(name));
+                    Some((ioc_type, value))
+                }
+                None if fields.len() >= 2 => {
+                    Some((IocType::from_name(&fields[0]), fields[1].clone()))
+                }
+                None => Some((IocType::infer(&fields[0]), fields[0].clone())),
+            }
+        })
+        .collect()
+}
+
+// Type of a STIX object path like domain-name:value or file:hashes.'SHA-256'
+fn stix_path_type(object: &str, property: &str) -> Option<IocType> {
+    let property = property.replace(['\'', '-'], "").to_lowercase();
+    if property.ends_with("ja3") {
+        return Some(IocType::Ja3);
+    }
+    if property.ends_with("ja4") {
+        return Some(IocType::Ja4);
+    }
+    match (object, property.as_str()) {
+        ("domain-name", "value") => Some(IocType::Domain),
+        ("ipv4-addr", "value") => Some(IocType::Ipv4),
+        ("file", "hashes.md5") => Some(IocType::Md5),
+        ("file", "hashes.sha256") => Some(IocType::Sha256),
+        _ => None,
+    }
+}
+
+fn parse_stix(content: &str) -> Result<Vec<Indicator>, String> {
+    let bundle: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse STIX bundle: {}", e))?;
+    let objects = match bundle.get("objects").and_then(|objects| objects.as_array()) {
+        Some(objects) => objects.clone(),
+        None => vec![bundle],
+    };
+
+    // Only equality comparisons, "[domain-name:value = 'evil.com']"
+    let comparison_re =
+        Regex::new(r#"([a-z0-9-]+):([A-Za-z0-9_.'-]+)\s*=\s*'((?:[^'\\]|\\.)*)'"#).unwrap();
+    let mut indicators = Vec::new();
+
+    for object in &objects {
+        let object_type = object.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        match object_type {
+            "indicator" => {
+                if object.get("revoked").and_then(|r| r.as_bool()) == Some(true) {
+                    continue;
+                }
+                let pattern_type = object
+                    .get("pattern_type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("stix");
+                let Some(pattern) = object.get("pattern").and_then(|p| p.as_str()) else {
+                    continue;
+                };
+                if pattern_type != "stix" {
+                    indicators.push((None, pattern.to_string()));
+                    continue;
+                }
+                for captures in comparison_re.captures_iter(pattern) {
+                    let value = captures[3].replace("\\'", "'").replace("\\\\", "\\");
+                    indicators.push((stix_path_type(&captures[1], &captures[2]), value));
+                }
+            }
+            "domain-name" | "ipv4-addr" => {
+                if let Some(value) = object.get("value").and_then(|v| v.as_str()) {
+                    indicators.push((stix_path_type(object_type, "value"), value.to_string()));
+                }
+            }
+            "file" => {
+                let Some(hashes) = object.get("hashes").and_then(|h| h.as_object()) else {
+                    continue;
+                };
+                for (algorithm, value) in hashes {
+                    if let Some(value) = value.as_str() {
+                        let property = format!("hashes.{}", algorithm);
+                        indicators.push((stix_path_type("file", &property), value.to_string()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(indicators)
+}
+
+fn rule(sid: u64, protocol: &str, msg: &str, detection: &str) -> String {
+    format!(
+        "alert {} any any -> any any (msg:\"{}\"; {} classtype:bad-unknown; metadata:category threat-intel; sid:{}; rev:1;)",
+        protocol, msg, detection, sid
+    )
+}
+
+fn dataset_match(buffer: &str, ioc_type: IocType) -> String {
+    let (name, dataset_type) = ioc_type.dataset();
+    let dataset_type = match dataset_type {
+        DatasetType::Ipv4 => "ipv4",
+        _ => "string",
+    };
+    format!("{}; dataset:isset,{},type {};", buffer, name, dataset_type)
+}
+
+// Rules matching traffic against one type of indicator
+fn rules_for(ioc_type: IocType) -> Result<Vec<String>, String> {
+    let base =
+        IOC_SID_BASE + IOC_TYPES.iter().position(|t| *t == ioc_type).unwrap_or(0) as u64 * 10;
+    let rules = match ioc_type {
+        IocType::Domain => vec![
+            rule(
+                base,
+                "dns",
+                "Threat intel domain in DNS query",
+                &dataset_match("dns.query", ioc_type),
+            ),
+            rule(
+                base + 1,
+                "tls",
+                "Threat intel domain in TLS SNI",
+                &dataset_match("tls.sni", ioc_type),
+            ),
+            rule(
+                base + 2,
+                "http",
+                "Threat intel domain in HTTP host",
+                &dataset_match("http.host", ioc_type),
+            ),
+        ],
+        IocType::Ipv4 => vec![
+            rule(
+                base,
+                "ip",
+                "Threat intel IP as destination",
+                &dataset_match("ip.dst", ioc_type),
+            ),
+            rule(
+                base + 1,
+                "ip",
+                "Threat intel IP as source",
+                &dataset_match("ip.src", ioc_type),
+            ),
+        ],
+        // filemd5 and filesha256 read the same one hash per line lists the
+        // datasets are stored in
+        IocType::Md5 | IocType::Sha256 => {
+            let (name, _) = ioc_type.dataset();
+            let (keyword, label) = match ioc_type {
+                IocType::Md5 => ("filemd5", "MD5"),
+                _ => ("filesha256", "SHA-256"),
+            };
+            let list = datasets::dataset_path(name)?
+                .display()
+                .to_string()
+                .replace('\\', "/");
+            FILE_PROTOCOLS
+                .iter()
+                .enumerate()
+                .map(|(i, protocol)| {
+                    rule(
+                        base + i as u64,
+                        protocol,
+                        &format!("Threat intel {} file hash over {}", label, protocol),
+                        &format!("{}:{};", keyword, list),
+                    )
+                })
+                .collect()
+        }
+        IocType::Ja3 => vec![rule(
+            base,
+            "tls",
+            "Threat intel JA3 fingerprint",
+            &dataset_match("ja3.hash", ioc_type),
+        )],
+        IocType::Ja4 => vec![rule(
+            base,
+            "tls",
+            "Threat intel JA4 fingerprint",
+            &dataset_match("ja4.hash", ioc_type),
+        )],
+    };
+    Ok(rules)
+}
+
+// The generated rules for every indicator type with entries, with source and
+// file markers, ready to be appended to the generated rules
+pub fn section() -> Result<String, String> {
+    let mut rules = Vec::new();
+    for ioc_type in IOC_TYPES {
+        if datasets::entry_count(ioc_type.dataset().0)? > 0 {
+            rules.extend(rules_for(*ioc_type)?);
+        }
+    }
+    if rules.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut section = String::new();
+    section.push_str(parser::SOURCE_MARKER);
+    section.push_str(IOC_SOURCE);
+    section.push('\n');
+    section.push_str(parser::FILE_MARKER);
+    section.push_str(RULE_FILE);
+    section.push('\n');
+    for rule in rules {
+        section.push_str(&rule);
+        section.push('\n');
+    }
+    Ok(section)
+}
+
+// Merge the current threat intel rules into suricata.rules and reload. The
+// caller holds the update lock.
+async fn apply(
+    app: &AppHandle,
+    rule_index: &RuleIndexState,
+    section: &str,
+) -> Result<Option<ReloadResult>, String> {
+    let settings = settings::load()?;
+    let rules_dir = paths::rules_dir()?;
+    std::fs::create_dir_all(&rules_dir)
+        .map_err(|e| format!("Failed to create rules directory: {}", e))?;
+    let suricata_rules_path = rules_dir.join("suricata.rules");
+    let current = std::fs::read_to_string(&suricata_rules_path).unwrap_or_default();
+
+    let policies = Policies::load()?;
+    let (section, _) = policy::apply_to_text(section, &policies);
+    let (merged, _) = dedup::dedup_text(
+        &local::replace_section(&current, IOC_SOURCE, &section),
+        settings.duplicate_strategy,
+        &source_priorities(&settings),
+    );
+
+    let staging_path = rules_dir.join("suricata.rules.staging");
+    validate::validate(app, &staging_path, merged, &settings.rule_validation).await?;
+    std::fs::rename(&staging_path, &suricata_rules_path)
+        .map_err(|e| format!("Failed to write suricata.rules: {}", e))?;
+    rule_index.invalidate();
+
+    Ok(reload::reload_changed(app).await)
+}
+
+// Import indicators from a CSV file or STIX bundle into the threat intel
+// datasets and activate rules matching them. With replace set the current
+// indicators of every type are dropped first.
+#[tauri::command]
+pub async fn import_iocs(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    path: String,
+    format: IocFormat,
+    replace: Option<bool>,
+) -> Result<IocImportReport, String> {
+    let replace = replace.unwrap_or(false);
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let indicators = match format {
+        IocFormat::Csv => parse_csv(&content),
+        IocFormat::Stix => parse_stix(&content)?,
+    };
+
+    let mut report = IocImportReport::default();
+    let mut values: BTreeMap<IocType, Vec<String>> = BTreeMap::new();
+    for (ioc_type, value) in indicators {
+        let Some(ioc_type) = ioc_type else {
+            report.skip(format!("Unsupported indicator: {}", value));
+            continue;
+        };
+        match ioc_type.normalize(&value) {
+            Ok(value) => values.entry(ioc_type).or_default().push(value),
+            Err(e) => report.skip(e),
+        }
+    }
+    if values.is_empty() && !replace {
+        return Err(format!("No supported indicators found in {}", path));
+    }
+
+    // The datasets are written under the update lock and put back if the
+    // rules using them don't validate
+    let update_lock = app.state::<UpdateLock>();
+    let _guard = update_lock.try_acquire()?;
+    let names: Vec<&str> = IOC_TYPES.iter().map(|t| t.dataset().0).collect();
+    let snapshot = datasets::snapshot(&names)?;
+    let applied = async {
+        for ioc_type in IOC_TYPES {
+            let values = values.remove(ioc_type).unwrap_or_default();
+            let (name, dataset_type) = ioc_type.dataset();
+            if values.is_empty() && !(replace && datasets::entry_count(name)? > 0) {
+                continue;
+            }
+            let dataset = datasets::ensure_dataset(name, dataset_type, "Threat intel indicators")?;
+            let result = datasets::add_to_dataset(&dataset, values, replace)?;
+            report.by_type.insert(*ioc_type, result);
+        }
+
+        let section = section()?;
+        report.rules = section
+            .lines()
+            .filter(|line| parser::Rule::parse(line).is_ok())
+            .count();
+        apply(&app, &rule_index, &section).await
+    }
+    .await;
+    report.reload = match applied {
+        Ok(reload) => reload,
+        Err(e) => {
+            return Err(match snapshot.restore() {
+                Ok(()) => e,
+                Err(restore) => format!("{}. {}", e, restore),
+            })
+        }
+    };
+    let added: usize = report.by_type.values().map(|result| result.added).sum();
+    report.message = format!(
+        "Imported {} indicators, skipped {}, {} threat intel rules active",
+        added, report.skipped, report.rules
+    );
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_ioc_summary() -> Result<Vec<IocSummary>, String> {
+    IOC_TYPES
+        .iter()
+        .map(|ioc_type| {
+            let name = ioc_type.dataset().0;
+            Ok(IocSummary {
+                ioc_type: *ioc_type,
+                dataset: name.to_string(),
+                entries: datasets::entry_count(name)?,
+            })
+        })
+        .collect()
+}
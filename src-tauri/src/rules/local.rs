@@ -0,0 +1,492 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tauri::{AppHandle, Manager, State};
+
+use super::index::RuleIndexState;
+use super::parser::{self, Rule};
+use super::policy::{self, Policies};
+use super::reload::{self, ReloadResult};
+use super::schedule::UpdateLock;
+use super::{dedup, source_priorities, validate};
+use crate::paths;
+use crate::settings;
+
+// Source name local rules are merged under
+pub const LOCAL_SOURCE: &str = "local";
+
+// Rule file used when no file is given
+const DEFAULT_FILE: &str = "local.rules";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SidRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+// 1000000-1999999 is the range set aside for local rules
+impl Default for SidRange {
+    fn default() -> Self {
+        Self {
+            start: 1_000_000,
+            end: 1_999_999,
+        }
+    }
+}
+
+impl SidRange {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start == 0 || self.start > self.end {
+            return Err(format!(
+                "Invalid local SID range {}-{}",
+                self.start, self.end
+            ));
+        }
+        Ok(())
+    }
+
+    // The next SID after the highest one in use, or the first unused one once
+    // the end of the range is reached
+    fn allocate(&self, used: &HashSet<u64>) -> Result<u64, String> {
+        let highest = used
+            .iter()
+            .copied()
+            .filter(|sid| (self.start..=self.end).contains(sid))
+            .max();
+        match highest {
+            None => return Ok(self.start),
+            Some(sid) if sid < self.end => return Ok(sid + 1),
+            _ => {}
+        }
+        (self.start..=self.end)
+            .find(|sid| !used.contains(sid))
+            .ok_or_else(|| {
+                format!(
+                    "No free SIDs left in the local range {}-{}",
+                    self.start, self.end
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalRuleFile {
+    pub name: String,
+    pub rules: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalRule {
+    pub file: String,
+    // The rule as it appears in the file
+    pub text: String,
+    pub rule: Rule,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalRuleChange {
+    pub sid: Option<u64>,
+    pub file: String,
+    pub message: String,
+    pub reload: Option<ReloadResult>,
+}
+
+pub fn validate_file_name(name: &str) -> Result<(), String> {
+    let valid = name.ends_with(".rules")
+        && name.len() > ".rules".len()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+        return Err(format!(
+            "Invalid rule file name {}, use letters, digits, - and _ ending in .rules",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// Local rule files by name
+fn read_files() -> Result<BTreeMap<String, String>, String> {
+    let dir = paths::local_rules_dir()?;
+    let mut files = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(files);
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || validate_file_name(name).is_err() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        files.insert(name.to_string(), content);
+    }
+    Ok(files)
+}
+
+fn local_rules(files: &BTreeMap<String, String>) -> Vec<LocalRule> {
+    let mut rules = Vec::new();
+    for (name, content) in files {
+        for line in content.lines() {
+            if let Ok(mut rule) = Rule::parse(line) {
+                rule.source = Some(LOCAL_SOURCE.to_string());
+                rule.file = Some(name.clone());
+                rules.push(LocalRule {
+                    file: name.clone(),
+                    text: line.trim().to_string(),
+                    rule,
+                });
+            }
+        }
+    }
+    rules
+}
+
+fn build_section(files: &BTreeMap<String, String>) -> String {
+    if files.values().all(|content| content.trim().is_empty()) {
+        return String::new();
+    }
+
+    let mut section = String::new();
+    section.push_str(parser::SOURCE_MARKER);
+    section.push_str(LOCAL_SOURCE);
+    section.push('\n');
+    for (name, content) in files {
+        section.push_str(parser::FILE_MARKER);
+        section.push_str(name);
+        section.push('\n');
+        section.push_str(content);
+        if !content.ends_with('\n') {
+            section.push('\n');
+        }
+    }
+    section
+}
+
+// The local rule files with source and file markers, ready to be appended to
+// the generated rules
+pub fn section() -> Result<String, String> {
+    Ok(build_section(&read_files()?))
+}
+
+// Swap the rules of one source in a generated rules file for a new section
+pub fn replace_section(text: &str, source: &str, section: &str) -> String {
+    let marker = format!("{}{}", parser::SOURCE_MARKER, source);
+    let mut out = String::with_capacity(text.len() + section.len());
+    let mut in_source = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(parser::SOURCE_MARKER.trim_end()) {
+            in_source = trimmed == marker;
+        }
+        if !in_source {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out.push_str(section);
+    out
+}
+
+// Replace the contents of one local rule file, or remove it when content is
+// None, and merge the result into suricata.rules. Nothing is changed on disk
+// unless Suricata accepts the new rules.
+async fn apply(
+    app: &AppHandle,
+    rule_index: &RuleIndexState,
+    name: &str,
+    content: Option<String>,
+) -> Result<Option<ReloadResult>, String> {
+    let update_lock = app.state::<UpdateLock>();
+    let _guard = update_lock.try_acquire()?;
+    let settings = settings::load()?;
+    let mut files = read_files()?;
+    match &content {
+        Some(content) => files.insert(name.to_string(), content.clone()),
+        None => files.remove(name),
+    };
+
+    let rules_dir = paths::rules_dir()?;
+    std::fs::create_dir_all(&rules_dir)
+        .map_err(|e| format!("Failed to create rules directory: {}", e))?;
+    let suricata_rules_path = rules_dir.join("suricata.rules");
+    let current = std::fs::read_to_string(&suricata_rules_path).unwrap_or_default();
+
+    let policies = Policies::load()?;
+    let (section, _) = policy::apply_to_text(&build_section(&files), &policies);
+    let (merged, _) = dedup::dedup_text(
+        &replace_section(&current, LOCAL_SOURCE, &section),
+        settings.duplicate_strategy,
+        &source_priorities(&settings),
+    );
+
+    let staging_path = rules_dir.join("suricata.rules.staging");
+    validate::validate(app, &staging_path, merged, &settings.rule_validation).await?;
+
+    let dir = paths::local_rules_dir()?;
+    let path = dir.join(name);
+    let written = match content {
+        Some(content) => std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&path, content))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e)),
+        None => std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete {}: {}", path.display(), e)),
+    };
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&staging_path);
+        return Err(e);
+    }
+
+    std::fs::rename(&staging_path, &suricata_rules_path)
+        .map_err(|e| format!("Failed to write suricata.rules: {}", e))?;
+    rule_index.invalidate();
+
+    Ok(reload::reload_changed(app).await)
+}
+
+// Parse a rule entered by the user. A rule without a sid gets the one from
+// default_sid and a rule without a rev gets rev 1.
+fn parse_local_rule(
+    text: &str,
+    default_sid: impl FnOnce() -> Result<u64, String>,
+) -> Result<Rule, String> {
+    let text = text.trim();
+    if text.contains('\n') {
+        return Err("A rule must be on a single line".to_string());
+    }
+
+    let mut rule = match Rule::parse(text) {
+        Ok(rule) => rule,
+        Err(e) if e == parser::MISSING_SID => {
+            let close = text
+                .rfind(')')
+                .ok_or_else(|| "Invalid rule: options must end with )".to_string())?;
+            let with_sid = format!("{} sid:{};)", text[..close].trim_end(), default_sid()?);
+            Rule::parse(&with_sid).map_err(|e| format!("Invalid rule: {}", e))?
+        }
+        Err(e) => return Err(format!("Invalid rule: {}", e)),
+    };
+    if !rule.options.iter().any(|option| option.name == "rev") {
+        rule.set_option("rev", "1")?;
+    }
+    Ok(rule)
+}
+
+// Make sure a rule doesn't reuse the gid:sid of another local rule, or of a
+// rule from one of the rule sources
+fn check_sid(
+    rule: &Rule,
+    local: &[LocalRule],
+    replacing: Option<(u64, u64)>,
+    rule_index: &RuleIndexState,
+) -> Result<(), String> {
+    let key = (rule.gid, rule.sid);
+    if Some(key) != replacing
+        && local
+            .iter()
+            .any(|existing| (existing.rule.gid, existing.rule.sid) == key)
+    {
+        return Err(format!("SID {} is already used by a local rule", rule.sid));
+    }
+
+    if let Ok(index) = rule_index.get() {
+        if let Some(existing) = index.get(rule.gid, rule.sid) {
+            if existing.source.as_deref() != Some(LOCAL_SOURCE) {
+                return Err(format!(
+                    "SID {} is already used by rule \"{}\" from {}",
+                    rule.sid,
+                    existing.msg,
+                    existing.source.as_deref().unwrap_or("an unknown source")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Replace or remove the line holding a rule
+fn edit_line(content: &str, text: &str, replacement: Option<&str>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut done = false;
+    for line in content.lines() {
+        if !done && line.trim() == text {
+            done = true;
+            if let Some(replacement) = replacement {
+                out.push_str(replacement);
+                out.push('\n');
+            }
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn find_local_rule(local: &[LocalRule], sid: u64, gid: Option<u64>) -> Result<&LocalRule, String> {
+    local
+        .iter()
+        .find(|existing| existing.rule.sid == sid && gid.is_none_or(|gid| existing.rule.gid == gid))
+        .ok_or_else(|| format!("Local rule {} not found", sid))
+}
+
+#[tauri::command]
+pub fn list_local_rule_files() -> Result<Vec<LocalRuleFile>, String> {
+    let mut files = read_files()?;
+    files.entry(DEFAULT_FILE.to_string()).or_default();
+    Ok(files
+        .iter()
+        .map(|(name, content)| LocalRuleFile {
+            name: name.clone(),
+            rules: content
+                .lines()
+                .filter(|line| Rule::parse(line).is_ok())
+                .count(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn create_local_rule_file(name: String) -> Result<String, String> {
+    validate_file_name(&name)?;
+    let dir = paths::local_rules_dir()?;
+    let path = dir.join(&name);
+    if path.exists() {
+        return Err(format!("Rule file {} already exists", name));
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create local rules directory: {}", e))?;
+    std::fs::write(&path, "").map_err(|e| format!("Failed to create {}: {}", name, e))?;
+    Ok(format!("Created rule file {}", name))
+}
+
+#[tauri::command]
+pub async fn delete_local_rule_file(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    name: String,
+) -> Result<LocalRuleChange, String> {
+    validate_file_name(&name)?;
+    if name == DEFAULT_FILE {
+        return Err(format!("{} can't be deleted", DEFAULT_FILE));
+    }
+    if !read_files()?.contains_key(&name) {
+        return Err(format!("Rule file {} not found", name));
+    }
+
+    let reload = apply(&app, &rule_index, &name, None).await?;
+    Ok(LocalRuleChange {
+        sid: None,
+        message: format!("Deleted rule file {}", name),
+        file: name,
+        reload,
+    })
+}
+
+#[tauri::command]
+pub fn get_local_rules(file: Option<String>) -> Result<Vec<LocalRule>, String> {
+    let rules = local_rules(&read_files()?);
+    Ok(match file {
+        Some(file) => rules.into_iter().filter(|rule| rule.file == file).collect(),
+        None => rules,
+    })
+}
+
+#[tauri::command]
+pub async fn add_local_rule(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    rule: String,
+    file: Option<String>,
+) -> Result<LocalRuleChange, String> {
+    let file = file.unwrap_or_else(|| DEFAULT_FILE.to_string());
+    validate_file_name(&file)?;
+    let settings = settings::load()?;
+    let files = read_files()?;
+    let local = local_rules(&files);
+
+    let rule = parse_local_rule(&rule, || {
+        let mut used: HashSet<u64> = local.iter().map(|existing| existing.rule.sid).collect();
+        if let Ok(index) = rule_index.get() {
+            used.extend(index.rules().iter().map(|rule| rule.sid));
+        }
+        settings.local_sid_range.allocate(&used)
+    })?;
+    check_sid(&rule, &local, None, &rule_index)?;
+
+    let mut content = files.get(&file).cloned().unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&rule.to_string());
+    content.push('\n');
+
+    let reload = apply(&app, &rule_index, &file, Some(content)).await?;
+    Ok(LocalRuleChange {
+        sid: Some(rule.sid),
+        file,
+        message: format!("Added local rule {}", rule.sid),
+        reload,
+    })
+}
+
+// Replace a local rule. The new rule keeps the old sid if it doesn't set one.
+#[tauri::command]
+pub async fn update_local_rule(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    sid: u64,
+    gid: Option<u64>,
+    rule: String,
+) -> Result<LocalRuleChange, String> {
+    let files = read_files()?;
+    let local = local_rules(&files);
+    let existing = find_local_rule(&local, sid, gid)?;
+
+    let rule = parse_local_rule(&rule, || Ok(sid))?;
+    check_sid(
+        &rule,
+        &local,
+        Some((existing.rule.gid, existing.rule.sid)),
+        &rule_index,
+    )?;
+
+    let content = edit_line(
+        &files[&existing.file],
+        &existing.text,
+        Some(&rule.to_string()),
+    );
+    let reload = apply(&app, &rule_index, &existing.file, Some(content)).await?;
+    Ok(LocalRuleChange {
+        sid: Some(rule.sid),
+        file: existing.file.clone(),
+        message: format!("Updated local rule {}", rule.sid),
+        reload,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_local_rule(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    sid: u64,
+    gid: Option<u64>,
+) -> Result<LocalRuleChange, String> {
+    let files = read_files()?;
+    let local = local_rules(&files);
+    let existing = find_local_rule(&local, sid, gid)?;
+
+    let content = edit_line(&files[&existing.file], &existing.text, None);
+    let reload = apply(&app, &rule_index, &existing.file, Some(content)).await?;
+    Ok(LocalRuleChange {
+        sid: Some(sid),
+        file: existing.file.clone(),
+        message: format!("Deleted local rule {}", sid),
+        reload,
+    })
+}
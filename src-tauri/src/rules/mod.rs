@@ -0,0 +1,657 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::http::HttpClient;
+use crate::paths;
+use crate::settings::{self, Settings};
+
+pub mod archive;
+mod cache;
+pub mod dedup;
+pub mod diff;
+pub mod index;
+pub mod ioc;
+pub mod local;
+pub mod parser;
+pub mod policy;
+pub mod reload;
+pub mod schedule;
+pub mod sources;
+pub mod stats;
+pub mod validate;
+pub mod versions;
+
+use archive::EntryKind;
+use cache::CachedSource;
+use dedup::DuplicateStats;
+use diff::RuleDiffSummary;
+use index::RuleIndexState;
+use policy::{Policies, PolicyStats};
+use reload::ReloadResult;
+use sources::{RuleSource, RuleSourceType};
+use stats::RulesetStats;
+use validate::ValidationReport;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FetchStatus {
+    // Downloaded new content
+    Fresh,
+    // The server returned 304, the cached copy is current
+    NotModified,
+    // The source failed, the last good cached copy was used
+    Cached,
+    // The source failed and there was no cached copy to fall back to
+    Failed,
+}
+
+struct FetchedSource {
+    data: Vec<u8>,
+    status: FetchStatus,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+// Rules and support files extracted from one source
+struct ProcessedSource {
+    rules: String,
+    rule_files: i32,
+    support_files: i32,
+}
+
+// Per-source outcome of a rule update
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceReport {
+    pub name: String,
+    pub url: String,
+    pub status: FetchStatus,
+    pub sha256: Option<String>,
+    // Why the source failed, also set when a cached copy was used instead
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateReport {
+    pub rules_path: String,
+    pub rule_files: i32,
+    pub sources: Vec<SourceReport>,
+    // Names of sources that failed, whether or not a cached copy was used
+    pub failed_sources: Vec<String>,
+    pub duplicates: DuplicateStats,
+    pub policy: PolicyStats,
+    pub diff: RuleDiffSummary,
+    pub stats: RulesetStats,
+    pub validation: ValidationReport,
+    // Snapshot the update was stored as, if storing it succeeded
+    pub version: Option<String>,
+    // Result of reloading a running Suricata, None when it isn't running
+    pub reload: Option<ReloadResult>,
+}
+
+// Download a rule source into memory, emitting progress events. A conditional
+// request is made when a cached copy exists, and the cached copy is returned
+// if the server reports it unchanged.
+async fn download_source(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    source: &RuleSource,
+    cached: Option<&CachedSource>,
+) -> Result<FetchedSource, String> {
+    let url = source.resolved_url()?;
+    let display_url = source.display_url();
+
+    let mut request = client.get(&url);
+    if let Some((name, value)) = source.auth_header()? {
+        request = request.header(name, value);
+    }
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.metadata.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.metadata.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download from {}: {}", display_url, e))?;
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+            let _ = app.emit(
+                "rules-update-progress",
+                serde_json::json!({
+                    "type": "info",
+                    "message": format!("{} is unchanged, using cached copy", display_url)
+                }),
+            );
+            return Ok(FetchedSource {
+                data: cached.data.clone(),
+                status: FetchStatus::NotModified,
+                etag: cached.metadata.etag.clone(),
+                last_modified: cached.metadata.last_modified.clone(),
+            });
+        }
+    }
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download from {}: HTTP {}",
+            display_url,
+            response.status()
+        ));
+    }
+
+    let header_value = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let etag = header_value(reqwest::header::ETAG);
+    let last_modified = header_value(reqwest::header::LAST_MODIFIED);
+
+    // Get content length for progress tracking
+    let total_size = response.content_length().unwrap_or(0);
+
+    // Downloads are held in memory, so they get the same limit as what an
+    // archive may unpack to. The declared size is only a hint.
+    let too_large = || {
+        format!(
+            "Download from {} is larger than {} MB",
+            display_url,
+            archive::MAX_TOTAL_SIZE / 1024 / 1024
+        )
+    };
+    if total_size > archive::MAX_TOTAL_SIZE {
+        return Err(too_large());
+    }
+
+    // Stream download with progress
+    let mut data = Vec::with_capacity(total_size as usize);
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to download from {}: {}", display_url, e))?;
+        if (data.len() + chunk.len()) as u64 > archive::MAX_TOTAL_SIZE {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+
+        // Emit progress
+        if total_size > 0 {
+            let downloaded = data.len() as u64;
+            let progress = (downloaded as f64 / total_size as f64 * 100.0) as u32;
+            let _ = app.emit(
+                "rules-update-progress",
+                serde_json::json!({
+                    "type": "download",
+                    "progress": progress,
+                    "downloaded": downloaded,
+                    "total": total_size,
+                    "url": display_url
+                }),
+            );
+        }
+    }
+
+    Ok(FetchedSource {
+        data,
+        status: FetchStatus::Fresh,
+        etag,
+        last_modified,
+    })
+}
+
+// Directory a source's support files are written to, so files from different
+// sources can't overwrite each other or anything else in the rules directory
+pub fn support_dir(rules_dir: &Path, source_name: &str) -> PathBuf {
+    rules_dir
+        .join("sources")
+        .join(cache::file_stem(source_name))
+}
+
+// Turn the downloaded content of a source into rules text. Archives are read
+// in memory, and their support files replace the ones the source provided
+// before.
+fn process_source(
+    source: &RuleSource,
+    data: &[u8],
+    rules_dir: &Path,
+) -> Result<ProcessedSource, String> {
+    if source.source_type == RuleSourceType::Rules {
+        // Handle direct .rules files, named after the last part of the URL
+        let file_name = source
+            .display_url()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let mut rules = format!("{}{}\n", parser::FILE_MARKER, file_name);
+        rules.push_str(&String::from_utf8_lossy(data));
+        rules.push('\n');
+        return Ok(ProcessedSource {
+            rules,
+            rule_files: 1,
+            support_files: 0,
+        });
+    }
+
+    let entries = archive::read_entries(source.source_type, data)?;
+    let mut processed = ProcessedSource {
+        rules: String::new(),
+        rule_files: 0,
+        support_files: 0,
+    };
+    let mut support = Vec::new();
+
+    for entry in entries {
+        match entry.kind {
+            EntryKind::Rules => {
+                // Mark where each file starts so rules can be traced back to it
+                processed.rules.push_str(parser::FILE_MARKER);
+                processed.rules.push_str(entry.file_name());
+                processed.rules.push('\n');
+                processed
+                    .rules
+                    .push_str(&String::from_utf8_lossy(&entry.data));
+                processed.rules.push('\n');
+                processed.rule_files += 1;
+            }
+            EntryKind::Support => support.push(entry),
+        }
+    }
+
+    // The directory is removed below, make sure it can only ever be one
+    // source's own
+    let dir = support_dir(rules_dir, &source.name);
+    let own_dir = dir
+        .strip_prefix(rules_dir.join("sources"))
+        .is_ok_and(|relative| {
+            let mut components = relative.components();
+            matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+        });
+    if !own_dir {
+        return Err(format!(
+            "Rule source {} can't be stored in {}",
+            source.name,
+            dir.display()
+        ));
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+    for entry in &support {
+        let path = dir.join(&entry.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, &entry.data)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        processed.support_files += 1;
+    }
+
+    Ok(processed)
+}
+
+// Priorities used to resolve duplicate SIDs. Local rules always win, see
+// dedup_text.
+fn source_priorities(settings: &Settings) -> HashMap<String, i32> {
+    settings
+        .rule_sources
+        .iter()
+        .map(|source| (source.name.clone(), source.priority))
+        .collect()
+}
+
+#[tauri::command]
+pub async fn update_rules(app: AppHandle) -> Result<UpdateReport, String> {
+    schedule::update_and_record(app, false).await
+}
+
+// Download, merge, validate and activate the rules from all enabled sources,
+// then reload a running Suricata. Used by both manual and scheduled updates.
+async fn run_update(app: AppHandle) -> Result<UpdateReport, String> {
+    let http_client = app.state::<HttpClient>();
+    let rule_index = app.state::<RuleIndexState>();
+    let settings = settings::load()?;
+    let sources: Vec<&RuleSource> = settings
+        .rule_sources
+        .iter()
+        .filter(|source| source.enabled)
+        .collect();
+
+    if sources.is_empty() {
+        return Err("No rule sources are enabled".to_string());
+    }
+
+    let rules_dir = paths::rules_dir()?;
+
+    // Create rules directory if it doesn't exist
+    std::fs::create_dir_all(&rules_dir)
+        .map_err(|e| format!("Failed to create rules directory: {}", e))?;
+
+    let client = http_client.get();
+
+    // Start with empty rules
+    let mut all_rules = String::new();
+    let mut total_rule_files = 0;
+    let mut source_reports = Vec::new();
+
+    // Process each source. A failing source falls back to its last good
+    // cached copy, or is left out, rather than failing the whole update.
+    for (index, source) in sources.iter().enumerate() {
+        let display_url = source.display_url();
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": format!("Downloading {} from: {}", source.name, display_url),
+                "url": display_url,
+                "current_source": index + 1,
+                "total_sources": sources.len()
+            }),
+        );
+
+        let cached = cache::load(&rules_dir, &source.name, &display_url);
+
+        let result = match download_source(&app, &client, source, cached.as_ref()).await {
+            Ok(fetched) => process_source(source, &fetched.data, &rules_dir)
+                .map(|processed| (fetched, processed)),
+            Err(e) => Err(e),
+        };
+
+        let mut report = SourceReport {
+            name: source.name.clone(),
+            url: display_url.clone(),
+            status: FetchStatus::Failed,
+            sha256: None,
+            error: None,
+        };
+
+        let processed = match result {
+            Ok((fetched, processed)) => {
+                // Only cache content that processed cleanly, so the cache
+                // always holds the last good copy
+                if fetched.status == FetchStatus::Fresh {
+                    if let Err(e) = cache::store(
+                        &rules_dir,
+                        &source.name,
+                        &display_url,
+                        fetched.etag,
+                        fetched.last_modified,
+                        &fetched.data,
+                    ) {
+                        let _ = app.emit(
+                            "rules-update-progress",
+                            serde_json::json!({
+                                "type": "info",
+                                "message": format!("Failed to cache {}: {}", source.name, e)
+                            }),
+                        );
+                    }
+                }
+                report.status = fetched.status;
+                report.sha256 = Some(cache::sha256_hex(&fetched.data));
+                Some(processed)
+            }
+            Err(e) => {
+                let _ = app.emit(
+                    "rules-update-progress",
+                    serde_json::json!({
+                        "type": "info",
+                        "message": format!("Failed to update {}: {}", source.name, e)
+                    }),
+                );
+                report.error = Some(e);
+
+                let fallback = cached.and_then(|cached| {
+                    process_source(source, &cached.data, &rules_dir)
+                        .ok()
+                        .map(|processed| (cached, processed))
+                });
+                match fallback {
+                    Some((cached, processed)) => {
+                        let _ = app.emit(
+                            "rules-update-progress",
+                            serde_json::json!({
+                                "type": "info",
+                                "message": format!("Using cached copy of {} from {}", source.name, cached.metadata.fetched_at)
+                            }),
+                        );
+                        report.status = FetchStatus::Cached;
+                        report.sha256 = Some(cached.metadata.sha256);
+                        Some(processed)
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        if let Some(processed) = processed {
+            all_rules.push_str(parser::SOURCE_MARKER);
+            all_rules.push_str(&source.name);
+            all_rules.push('\n');
+            all_rules.push_str(&processed.rules);
+            total_rule_files += processed.rule_files;
+
+            if processed.support_files > 0 {
+                let _ = app.emit(
+                    "rules-update-progress",
+                    serde_json::json!({
+                        "type": "info",
+                        "message": format!("Copied {} support files from: {}", processed.support_files, display_url)
+                    }),
+                );
+            }
+        }
+
+        source_reports.push(report);
+    }
+
+    let failed_sources: Vec<String> = source_reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .map(|report| report.name.clone())
+        .collect();
+
+    // Don't replace the current rules with an empty file if nothing could be
+    // fetched at all
+    if source_reports
+        .iter()
+        .all(|report| report.status == FetchStatus::Failed)
+    {
+        return Err(format!(
+            "Rule update failed, no sources could be downloaded: {}",
+            failed_sources.join(", ")
+        ));
+    }
+
+    // Local rules are merged into every update
+    all_rules.push_str(&local::section()?);
+    // As are the rules generated from imported threat intel
+    all_rules.push_str(&ioc::section()?);
+
+    // Remove rules provided by more than one source
+    let (all_rules, duplicate_stats) = dedup::dedup_text(
+        &all_rules,
+        settings.duplicate_strategy,
+        &source_priorities(&settings),
+    );
+    if duplicate_stats.duplicate_sids > 0 {
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": format!(
+                    "Found {} duplicate SIDs, removed {} duplicate rules",
+                    duplicate_stats.duplicate_sids,
+                    duplicate_stats.removed
+                )
+            }),
+        );
+    }
+
+    // Apply the disable, enable, modify and drop policies
+    let policies = Policies::load()?;
+    let (all_rules, policy_stats) = policy::apply_to_text(&all_rules, &policies);
+    if !policies.is_empty() {
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": format!(
+                    "Applied rule policies: {} disabled, {} enabled, {} modified, {} converted to drop",
+                    policy_stats.disabled,
+                    policy_stats.enabled,
+                    policy_stats.modified,
+                    policy_stats.dropped
+                )
+            }),
+        );
+        for error in &policy_stats.errors {
+            let _ = app.emit(
+                "rules-update-progress",
+                serde_json::json!({
+                    "type": "info",
+                    "message": error
+                }),
+            );
+        }
+    }
+
+    // Test the rules in a staging file so a ruleset Suricata can't load
+    // never replaces the current one
+    let staging_path = rules_dir.join("suricata.rules.staging");
+    let (all_rules, validation) =
+        validate::validate(&app, &staging_path, all_rules, &settings.rule_validation).await?;
+
+    // Compare against the previous ruleset before it is overwritten
+    let suricata_rules_path = rules_dir.join("suricata.rules");
+    let previous_rules = std::fs::read_to_string(&suricata_rules_path).unwrap_or_default();
+    let new_rules = parser::parse_rules(&all_rules, None);
+    let rule_diff = diff::diff(&parser::parse_rules(&previous_rules, None), &new_rules);
+    let stats = stats::ruleset_stats(&new_rules);
+    let diff_summary = match diff::save(&rule_diff) {
+        Ok(path) => rule_diff.summary(Some(&path)),
+        Err(e) => {
+            let _ = app.emit(
+                "rules-update-progress",
+                serde_json::json!({
+                    "type": "info",
+                    "message": e
+                }),
+            );
+            rule_diff.summary(None)
+        }
+    };
+
+    // Promote the staging file to suricata.rules
+    std::fs::rename(&staging_path, &suricata_rules_path)
+        .map_err(|e| format!("Failed to write suricata.rules: {}", e))?;
+    rule_index.invalidate();
+
+    // Keep a snapshot so the update can be rolled back
+    let version = match versions::snapshot(&rules_dir, &all_rules, &source_reports) {
+        Ok(version) => Some(version.version),
+        Err(e) => {
+            let _ = app.emit(
+                "rules-update-progress",
+                serde_json::json!({
+                    "type": "info",
+                    "message": e
+                }),
+            );
+            None
+        }
+    };
+
+    // Load the new rules into a running Suricata without restarting it
+    let reload = reload::reload_changed(&app).await;
+
+    let fresh_count = source_reports
+        .iter()
+        .filter(|report| report.status == FetchStatus::Fresh)
+        .count();
+    let cached_count = source_reports
+        .iter()
+        .filter(|report| {
+            report.status == FetchStatus::NotModified || report.status == FetchStatus::Cached
+        })
+        .count();
+
+    let mut message = format!(
+        "Rules updated successfully! Processed {} rule files from {} sources ({} fresh, {} cached).",
+        total_rule_files,
+        sources.len(),
+        fresh_count,
+        cached_count
+    );
+    message.push_str(&format!(
+        " {} rules, {} enabled.",
+        stats.total, stats.enabled
+    ));
+    if !failed_sources.is_empty() {
+        message.push_str(&format!(" Failed sources: {}.", failed_sources.join(", ")));
+    }
+    if duplicate_stats.removed > 0 {
+        message.push_str(&format!(
+            " Removed {} duplicate rules.",
+            duplicate_stats.removed
+        ));
+    }
+    if !validation.disabled.is_empty() {
+        message.push_str(&format!(
+            " Disabled {} rules Suricata failed to load.",
+            validation.disabled.len()
+        ));
+    }
+    if validation.skipped {
+        message.push_str(" Rules were not tested with Suricata.");
+    }
+
+    message.push_str(&format!(
+        " {} added, {} removed, {} modified, {} enabled, {} disabled.",
+        diff_summary.added,
+        diff_summary.removed,
+        diff_summary.modified,
+        diff_summary.enabled,
+        diff_summary.disabled
+    ));
+
+    if let Some(reload) = &reload {
+        message.push_str(&format!(" {}.", reload.message));
+    }
+
+    let report = UpdateReport {
+        rules_path: suricata_rules_path.display().to_string(),
+        rule_files: total_rule_files,
+        sources: source_reports,
+        failed_sources,
+        duplicates: duplicate_stats,
+        policy: policy_stats,
+        diff: diff_summary,
+        stats,
+        validation,
+        version,
+        reload,
+    };
+
+    let _ = app.emit("rules-update-diff", &rule_diff);
+
+    let _ = app.emit(
+        "rules-update-progress",
+        serde_json::json!({
+            "type": "complete",
+            "message": message,
+            "report": report
+        }),
+    );
+
+    Ok(report)
+}
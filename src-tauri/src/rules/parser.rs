@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Comment lines written into the generated suricata.rules so rules can be
+// traced back to the source and file they came from
+pub const SOURCE_MARKER: &str = "# meerkat-source: ";
+pub const FILE_MARKER: &str = "# meerkat-file: ";
+
+const ACTIONS: &[&str] = &[
+    "alert",
+    "pass",
+    "drop",
+    "reject",
+    "rejectsrc",
+    "rejectdst",
+    "rejectboth",
+    "config",
+];
+
+const DIRECTIONS: &[&str] = &["->", "<>", "=>"];
+
+// Error returned for rules without a sid option
+pub const MISSING_SID: &str = "Rule has no sid";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleOption {
+    pub name: String,
+    // Raw value as written in the rule, including any quotes. None for
+    // options without a value, like "nocase".
+    pub value: Option<String>,
+}
+
+// A parsed Suricata rule. The options are the source of truth for
+// serialization; sid, gid, rev, msg, classtype and metadata are derived from
+// them for convenience.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub enabled: bool,
+    pub action: String,
+    pub protocol: String,
+    pub src_addr: String,
+    pub src_port: String,
+    pub direction: String,
+    pub dst_addr: String,
+    pub dst_port: String,
+    pub options: Vec<RuleOption>,
+    pub sid: u64,
+    pub gid: u64,
+    pub rev: u64,
+    pub msg: String,
+    pub classtype: Option<String>,
+    pub metadata: Vec<(String, String)>,
+    // Name of the rule source and file the rule was loaded from, if known
+    pub source: Option<String>,
+    pub file: Option<String>,
+}
+
+impl Rule {
+    // Parse a single rule line. Commented out rules parse as disabled rules.
+    pub fn parse(line: &str) -> Result<Rule, String> {
+        let trimmed = line.trim();
+        let (enabled, body) = if trimmed.starts_with('#') {
+            (false, trimmed.trim_start_matches('#').trim_start())
+        } else {
+            (true, trimmed)
+        };
+
+        let open = body
+            .find('(')
+            .ok_or_else(|| "Rule has no options".to_string())?;
+        if !body.ends_with(')') {
+            return Err("Rule options must end with )".to_string());
+        }
+        let close = body.len() - 1;
+
+        let header = tokenize_header(&body[..open]);
+        if header.len() != 7 {
+            return Err(format!(
+                "Rule header must have 7 fields, found {}",
+                header.len()
+            ));
+        }
+        if !ACTIONS.contains(&header[0].as_str()) {
+            return Err(format!("Unknown rule action: {}", header[0]));
+        }
+        if !DIRECTIONS.contains(&header[4].as_str()) {
+            return Err(format!("Invalid rule direction: {}", header[4]));
+        }
+
+        let options = split_options(&body[open + 1..close])?;
+
+        let mut header = header.into_iter();
+        let mut rule = Rule {
+            enabled,
+            action: header.next().unwrap_or_default(),
+            protocol: header.next().unwrap_or_default(),
+            src_addr: header.next().unwrap_or_default(),
+            src_port: header.next().unwrap_or_default(),
+            direction: header.next().unwrap_or_default(),
+            dst_addr: header.next().unwrap_or_default(),
+            dst_port: header.next().unwrap_or_default(),
+            options,
+            sid: 0,
+            gid: 1,
+            rev: 0,
+            msg: String::new(),
+            classtype: None,
+            metadata: Vec::new(),
+            source: None,
+            file: None,
+        };
+        rule.refresh()?;
+        Ok(rule)
+    }
+
+    // Recompute the derived fields from the options
+    pub fn refresh(&mut self) -> Result<(), String> {
+        let parse_number = |name: &str, value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid {} value: {}", name, value))
+        };
+
+        self.sid = 0;
+        self.gid = 1;
+        self.rev = 0;
+        self.msg = String::new();
+        self.classtype = None;
+        self.metadata = Vec::new();
+
+        for option in &self.options {
+            let value = option.value.as_deref().unwrap_or("");
+            match option.name.as_str() {
+                "sid" => self.sid = parse_number("sid", value)?,
+                "gid" => self.gid = parse_number("gid", value)?,
+                "rev" => self.rev = parse_number("rev", value)?,
+                "msg" => self.msg = unquote(value),
+                "classtype" => self.classtype = Some(value.trim().to_string()),
+                "metadata" => {
+                    for entry in value.split(',') {
+                        let entry = entry.trim();
+                        if entry.is_empty() {
+                            continue;
+                        }
+                        let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+                        self.metadata
+                            .push((key.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if self.sid == 0 {
+            return Err(MISSING_SID.to_string());
+        }
+        Ok(())
+    }
+
+    // Set the raw value of an option, adding it after the existing options if
+    // the rule doesn't have it yet
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match self.options.iter_mut().find(|option| option.name == name) {
+            Some(option) => option.value = Some(value.to_string()),
+            None => self.options.push(RuleOption {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+            }),
+        }
+        self.refresh()
+    }
+
+    pub fn metadata_values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.metadata
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.enabled {
+            write!(f, "# ")?;
+        }
+        write!(
+            f,
+            "{} {} {} {} {} {} {} (",
+            self.action,
+            self.protocol,
+            self.src_addr,
+            self.src_port,
+            self.direction,
+            self.dst_addr,
+            self.dst_port
+        )?;
+        for (i, option) in self.options.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match &option.value {
+                Some(value) => write!(f, "{}:{};", option.name, value)?,
+                None => write!(f, "{};", option.name)?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+// Split a rule header on whitespace, keeping bracketed lists like
+// "[10.0.0.0/8, 192.168.0.0/16]" together
+fn tokenize_header(header: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in header.chars() {
+        match c {
+            '[' => {
+                depth += 1;
+                current.push(c);
+            }
+            ']' => {
+                depth -= 1;
+                current.push(c);
+            }
+            c if c.is_whitespace() && depth <= 0 => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+// Split the option body on unescaped semicolons, the same way Suricata does
+fn split_options(body: &str) -> Result<Vec<RuleOption>, String> {
+    let mut options = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+
+    for c in body.chars() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => {
+                current.push(c);
+                escaped = true;
+            }
+            ';' => {
+                push_option(&mut options, &current);
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.trim().is_empty() {
+        return Err(format!(
+            "Rule option not terminated with ;: {}",
+            current.trim()
+        ));
+    }
+    Ok(options)
+}
+
+fn push_option(options: &mut Vec<RuleOption>, option: &str) {
+    let option = option.trim();
+    if option.is_empty() {
+        return;
+    }
+    let (name, value) = match option.split_once(':') {
+        Some((name, value)) => (name.trim(), Some(value.trim().to_string())),
+        None => (option, None),
+    };
+    options.push(RuleOption {
+        name: name.to_string(),
+        value,
+    });
+}
+
+// Strip the quotes from an option value and remove escaping
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    let mut out = String::with_capacity(value.len());
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            out.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Parse every rule in a rules file. Lines that aren't rules, including
+// ordinary comments, are skipped. Source and file markers written by the rule
+// update set the source and file of the rules that follow them.
+pub fn parse_rules(text: &str, source: Option<&str>) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    let mut current_source = source.map(|s| s.to_string());
+    let mut current_file: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix(SOURCE_MARKER.trim_end()) {
+            current_source = Some(name.trim().to_string());
+            current_file = None;
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix(FILE_MARKER.trim_end()) {
+            current_file = Some(name.trim().to_string());
+            continue;
+        }
+        if let Ok(mut rule) = Rule::parse(trimmed) {
+            rule.source = current_source.clone();
+            rule.file = current_file.clone();
+            rules.push(rule);
+        }
+    }
+
+    rules
+}
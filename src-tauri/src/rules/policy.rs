@@ -0,0 +1,517 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::State;
+
+use super::index::RuleIndexState;
+use super::parser::{self, Rule};
+use crate::paths;
+
+// The policy files, named and formatted like suricata-update's
+// disable.conf, enable.conf, drop.conf and modify.conf
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyKind {
+    Disable,
+    Enable,
+    Drop,
+    Modify,
+}
+
+impl PolicyKind {
+    fn file_name(self) -> &'static str {
+        match self {
+            PolicyKind::Disable => "disable.conf",
+            PolicyKind::Enable => "enable.conf",
+            PolicyKind::Drop => "drop.conf",
+            PolicyKind::Modify => "modify.conf",
+        }
+    }
+
+    fn path(self) -> Result<PathBuf, String> {
+        Ok(paths::data_dir()?.join(self.file_name()))
+    }
+}
+
+// Selects the rules a policy line applies to. Supported forms:
+//   2019401 or 1:2019401     - by sid, or gid:sid
+//   group:emerging-icmp.rules - by rules file or source name, * and ? wildcards
+//   re:heartbleed             - regular expression on the rule text
+//   metadata: key value       - by metadata key and value
+//   classtype:trojan-activity - by classtype
+#[derive(Debug, Clone)]
+enum Matcher {
+    Sid { gid: Option<u64>, sid: u64 },
+    Group(Regex),
+    Regex(Regex),
+    Metadata { key: String, value: String },
+    Classtype(String),
+}
+
+impl Matcher {
+    fn parse(text: &str) -> Result<Matcher, String> {
+        let text = text.trim();
+        if let Some(pattern) = text.strip_prefix("re:") {
+            let regex = Regex::new(pattern.trim())
+                .map_err(|e| format!("Invalid regular expression {}: {}", pattern, e))?;
+            return Ok(Matcher::Regex(regex));
+        }
+        if let Some(group) = text.strip_prefix("group:") {
+            return Ok(Matcher::Group(glob_to_regex(group.trim())?));
+        }
+        if let Some(metadata) = text.strip_prefix("metadata:") {
+            let metadata = metadata.trim();
+            let (key, value) = metadata
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("Expected \"metadata: key value\", got: {}", text))?;
+            return Ok(Matcher::Metadata {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            });
+        }
+        if let Some(classtype) = text.strip_prefix("classtype:") {
+            return Ok(Matcher::Classtype(classtype.trim().to_string()));
+        }
+
+        let parse_number = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid rule matcher: {}", text))
+        };
+        match text.split_once(':') {
+            Some((gid, sid)) => Ok(Matcher::Sid {
+                gid: Some(parse_number(gid)?),
+                sid: parse_number(sid)?,
+            }),
+            None => Ok(Matcher::Sid {
+                gid: None,
+                sid: parse_number(text)?,
+            }),
+        }
+    }
+
+    fn matches(&self, rule: &Rule, text: &str) -> bool {
+        match self {
+            Matcher::Sid { gid, sid } => rule.sid == *sid && gid.is_none_or(|g| rule.gid == g),
+            Matcher::Group(regex) => {
+                rule.file
+                    .as_deref()
+                    .is_some_and(|file| regex.is_match(file))
+                    || rule
+                        .source
+                        .as_deref()
+                        .is_some_and(|source| regex.is_match(source))
+            }
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Metadata { key, value } => rule
+                .metadata_values(key)
+                .any(|v| v.eq_ignore_ascii_case(value)),
+            Matcher::Classtype(classtype) => rule.classtype.as_deref() == Some(classtype.as_str()),
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| format!("Invalid group pattern {}: {}", glob, e))
+}
+
+#[derive(Debug, Clone)]
+struct PolicyEntry {
+    line: usize,
+    text: String,
+    matcher: Matcher,
+    // Only set for modify.conf entries
+    modify: Option<(Regex, String)>,
+}
+
+// Parse the content of a policy file. Blank lines and # comments are ignored.
+fn parse_policy(kind: PolicyKind, content: &str) -> Result<Vec<PolicyEntry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let (matcher, modify) = if kind == PolicyKind::Modify {
+            let (matcher, from, to) = parse_modify_line(text)
+                .map_err(|e| format!("{} line {}: {}", kind.file_name(), i + 1, e))?;
+            let from = Regex::new(&from).map_err(|e| {
+                format!(
+                    "{} line {}: invalid regular expression: {}",
+                    kind.file_name(),
+                    i + 1,
+                    e
+                )
+            })?;
+            (matcher, Some((from, python_replacement(&to))))
+        } else {
+            (text.to_string(), None)
+        };
+        let matcher = Matcher::parse(&matcher)
+            .map_err(|e| format!("{} line {}: {}", kind.file_name(), i + 1, e))?;
+        entries.push(PolicyEntry {
+            line: i + 1,
+            text: text.to_string(),
+            matcher,
+            modify,
+        });
+    }
+    Ok(entries)
+}
+
+// Check the content of a policy file given by its file name
+pub fn validate_file(file_name: &str, content: &str) -> Result<(), String> {
+    let kind = [
+        PolicyKind::Disable,
+        PolicyKind::Enable,
+        PolicyKind::Drop,
+        PolicyKind::Modify,
+    ]
+    .into_iter()
+    .find(|kind| kind.file_name() == file_name)
+    .ok_or_else(|| format!("Unknown policy file {}", file_name))?;
+    parse_policy(kind, content).map(|_| ())
+}
+
+// Split a modify.conf line of the form: <matcher> "<from regex>" "<to>"
+fn parse_modify_line(line: &str) -> Result<(String, String, String), String> {
+    let quote = line
+        .find('"')
+        .ok_or_else(|| "Expected: <matcher> \"<from>\" \"<to>\"".to_string())?;
+    let matcher = line[..quote].trim().to_string();
+    if matcher.is_empty() {
+        return Err("Missing rule matcher".to_string());
+    }
+
+    let mut strings = Vec::new();
+    let mut rest = &line[quote..];
+    while let Some(start) = rest.find('"') {
+        let body = &rest[start + 1..];
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in body.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                end = Some(i);
+                break;
+            }
+        }
+        let end = end.ok_or_else(|| "Unterminated quoted string".to_string())?;
+        strings.push(body[..end].replace("\\\"", "\""));
+        rest = &body[end + 1..];
+    }
+
+    match <[String; 2]>::try_from(strings) {
+        Ok([from, to]) => Ok((matcher, from, to)),
+        Err(_) => Err("Expected: <matcher> \"<from>\" \"<to>\"".to_string()),
+    }
+}
+
+// Translate a replacement written for Python's re.sub, as suricata-update
+// uses, into the regex crate's syntax: \1 and \g<name> become ${1} and
+// ${name}, and a literal $ is escaped
+fn python_replacement(to: &str) -> String {
+    let mut out = String::with_capacity(to.len());
+    let mut chars = to.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '$' => out.push_str("$$"),
+            '\\' => match chars.peek() {
+                Some(d) if d.is_ascii_digit() => {
+                    let mut group = String::new();
+                    while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                        group.push(d);
+                    }
+                    out.push_str(&format!("${{{}}}", group));
+                }
+                Some('g') => {
+                    let rest: String = chars.clone().skip(1).collect();
+                    match rest.strip_prefix('<').and_then(|r| r.split_once('>')) {
+                        Some((name, _)) if !name.is_empty() => {
+                            out.push_str(&format!("${{{}}}", name));
+                            // Skip g<name>
+                            for _ in 0..name.chars().count() + 3 {
+                                chars.next();
+                            }
+                        }
+                        _ => out.push('\\'),
+                    }
+                }
+                Some('\\') => {
+                    chars.next();
+                    out.push('\\');
+                }
+                _ => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// All policies, loaded from the policy files
+pub struct Policies {
+    disable: Vec<PolicyEntry>,
+    enable: Vec<PolicyEntry>,
+    drop: Vec<PolicyEntry>,
+    modify: Vec<PolicyEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PolicyStats {
+    pub disabled: usize,
+    pub enabled: usize,
+    pub modified: usize,
+    pub dropped: usize,
+    // Modifications that produced an invalid rule and were skipped
+    pub errors: Vec<String>,
+}
+
+impl Policies {
+    pub fn load() -> Result<Policies, String> {
+        let load = |kind: PolicyKind| -> Result<Vec<PolicyEntry>, String> {
+            let path = kind.path()?;
+            if !path.exists() {
+                return Ok(Vec::new());
+            }
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            parse_policy(kind, &content)
+        };
+        Ok(Policies {
+            disable: load(PolicyKind::Disable)?,
+            enable: load(PolicyKind::Enable)?,
+            drop: load(PolicyKind::Drop)?,
+            modify: load(PolicyKind::Modify)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.disable.is_empty()
+            && self.enable.is_empty()
+            && self.drop.is_empty()
+            && self.modify.is_empty()
+    }
+
+    // Apply the policies to one rule, in the same order as suricata-update:
+    // disable, enable, modify, drop. Returns true if the rule changed.
+    pub fn apply(&self, rule: &mut Rule, stats: &mut PolicyStats) -> bool {
+        let original = rule.clone();
+        let matches = |entries: &[PolicyEntry], rule: &Rule| {
+            let text = rule.to_string();
+            entries
+                .iter()
+                .any(|entry| entry.matcher.matches(rule, &text))
+        };
+
+        if rule.enabled && matches(&self.disable, rule) {
+            rule.enabled = false;
+            stats.disabled += 1;
+        }
+        if !rule.enabled && matches(&self.enable, rule) {
+            rule.enabled = true;
+            stats.enabled += 1;
+        }
+
+        let mut modified = false;
+        for entry in &self.modify {
+            let text = rule.to_string();
+            if !entry.matcher.matches(rule, &text) {
+                continue;
+            }
+            let Some((from, to)) = &entry.modify else {
+                continue;
+            };
+            let replaced = from.replace_all(&text, to.as_str());
+            if replaced == text {
+                continue;
+            }
+            match Rule::parse(&replaced) {
+                Ok(mut new_rule) => {
+                    new_rule.source = rule.source.clone();
+                    new_rule.file = rule.file.clone();
+                    *rule = new_rule;
+                    modified = true;
+                }
+                Err(e) => stats.errors.push(format!(
+                    "modify.conf line {} produced an invalid rule for sid {}: {}",
+                    entry.line, rule.sid, e
+                )),
+            }
+        }
+        if modified {
+            stats.modified += 1;
+        }
+
+        if rule.enabled && rule.action == "alert" && matches(&self.drop, rule) {
+            rule.action = "drop".to_string();
+            stats.dropped += 1;
+        }
+
+        *rule != original
+    }
+}
+
+// Apply the policies to a rules file, rewriting only the rules that change.
+// Everything else, including comments and rules the parser can't handle, is
+// kept as is.
+pub fn apply_to_text(text: &str, policies: &Policies) -> (String, PolicyStats) {
+    let mut stats = PolicyStats::default();
+    if policies.is_empty() {
+        return (text.to_string(), stats);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut current_source: Option<String> = None;
+    let mut current_file: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix(parser::SOURCE_MARKER.trim_end()) {
+            current_source = Some(name.trim().to_string());
+            current_file = None;
+        } else if let Some(name) = trimmed.strip_prefix(parser::FILE_MARKER.trim_end()) {
+            current_file = Some(name.trim().to_string());
+        } else if let Ok(mut rule) = Rule::parse(trimmed) {
+            rule.source = current_source.clone();
+            rule.file = current_file.clone();
+            if policies.apply(&mut rule, &mut stats) {
+                out.push_str(&rule.to_string());
+                out.push('\n');
+                continue;
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    (out, stats)
+}
+
+#[tauri::command]
+pub fn get_rule_policy(kind: PolicyKind) -> Result<String, String> {
+    let path = kind.path()?;
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", kind.file_name(), e))
+}
+
+// Save a policy file. The content is validated first; policies take effect on
+// the next rule update.
+#[tauri::command]
+pub fn save_rule_policy(kind: PolicyKind, content: String) -> Result<String, String> {
+    parse_policy(kind, &content)?;
+    let path = kind.path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", kind.file_name(), e))?;
+    Ok(format!("Saved {}", kind.file_name()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyEntryPreview {
+    pub line: usize,
+    pub text: String,
+    // Number of rules the entry matches
+    pub matched: usize,
+    // SIDs of the first matching rules
+    pub sids: Vec<u64>,
+}
+
+// Show which of the current rules each line of a policy would affect, without
+// saving it
+#[tauri::command]
+pub async fn preview_rule_policy(
+    rule_index: State<'_, RuleIndexState>,
+    kind: PolicyKind,
+    content: String,
+) -> Result<Vec<PolicyEntryPreview>, String> {
+    const MAX_SIDS: usize = 50;
+
+    let entries = parse_policy(kind, &content)?;
+    let index = rule_index.get()?;
+    let texts: Vec<String> = index.rules().iter().map(|rule| rule.to_string()).collect();
+
+    Ok(entries
+        .iter()
+        .map(|entry| {
+            let mut matched = 0;
+            let mut sids = Vec::new();
+            for (rule, text) in index.rules().iter().zip(&texts) {
+                if !entry.matcher.matches(rule, text) {
+                    continue;
+                }
+                // A modify entry only affects rules its pattern changes
+                if let Some((from, _)) = &entry.modify {
+                    if !from.is_match(text) {
+                        continue;
+                    }
+                }
+                matched += 1;
+                if sids.len() < MAX_SIDS {
+                    sids.push(rule.sid);
+                }
+            }
+            PolicyEntryPreview {
+                line: entry.line,
+                text: entry.text.clone(),
+                matched,
+                sids,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modify(content: &str) -> Policies {
+        Policies {
+            disable: Vec::new(),
+            enable: Vec::new(),
+            drop: Vec::new(),
+            modify: parse_policy(PolicyKind::Modify, content).unwrap(),
+        }
+    }
+
+    #[test]
+    fn python_replacement_translates_groups() {
+        assert_eq!(python_replacement(r"\1 \2"), "${1} ${2}");
+        assert_eq!(python_replacement(r"\12x"), "${12}x");
+        assert_eq!(python_replacement(r"\g<1>0 \g<name>"), "${1}0 ${name}");
+        assert_eq!(python_replacement(r"a\\b $1"), r"a\b $$1");
+        assert_eq!(python_replacement(r"\n \g<"), r"\n \g<");
+    }
+
+    #[test]
+    fn modify_uses_python_group_references() {
+        let policies = modify("# Turn alerts into drops\n2019401 \"^alert(.*)$\" \"drop\\1\"\n");
+        let text = "alert tcp any any -> any any (msg:\"test\"; sid:2019401; rev:1;)\n";
+        let (out, stats) = apply_to_text(text, &policies);
+        assert_eq!(
+            out,
+            "drop tcp any any -> any any (msg:\"test\"; sid:2019401; rev:1;)\n"
+        );
+        assert_eq!(stats.modified, 1);
+        assert!(stats.errors.is_empty());
+    }
+}
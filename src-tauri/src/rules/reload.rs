@@ -0,0 +1,204 @@
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(windows)]
+use uds_windows::UnixStream;
+
+use crate::paths;
+
+const PROTOCOL_VERSION: &str = "0.2";
+
+// reload-rules only answers once the new ruleset is loaded, which takes a
+// while for large rulesets
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadResult {
+    pub reloaded: bool,
+    pub rules_loaded: Option<u64>,
+    pub rules_failed: Option<u64>,
+    pub message: String,
+}
+
+// Client for Suricata's unix socket command protocol. Every message is a JSON
+// object answered by {"return": "OK" | "NOK", "message": ...}.
+struct CommandClient {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+}
+
+impl CommandClient {
+    fn connect(path: &Path) -> Result<Self, String> {
+        let stream = UnixStream::connect(path)
+            .map_err(|e| format!("Failed to connect to Suricata command socket: {}", e))?;
+        stream
+            .set_read_timeout(Some(TIMEOUT))
+            .map_err(|e| format!("Failed to configure Suricata command socket: {}", e))?;
+
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+        };
+        client.send(serde_json::json!({ "version": PROTOCOL_VERSION }))?;
+        Ok(client)
+    }
+
+    fn command(&mut self, command: &str) -> Result<serde_json::Value, String> {
+        self.send(serde_json::json!({ "command": command }))
+    }
+
+    // Send a message and return the message field of the reply
+    fn send(&mut self, message: serde_json::Value) -> Result<serde_json::Value, String> {
+        self.stream
+            .write_all(message.to_string().as_bytes())
+            .map_err(|e| format!("Failed to write to Suricata command socket: {}", e))?;
+
+        let reply = self.read_reply()?;
+        let message = reply
+            .get("message")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        if reply.get("return").and_then(|r| r.as_str()) != Some("OK") {
+            return Err(format!("Suricata rejected the command: {}", message));
+        }
+        Ok(message)
+    }
+
+    // Replies aren't length prefixed, so read until the buffer holds a
+    // complete JSON value
+    fn read_reply(&mut self) -> Result<serde_json::Value, String> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Ok(value) = serde_json::from_slice(&self.buffer) {
+                self.buffer.clear();
+                return Ok(value);
+            }
+            let n = self
+                .stream
+                .read(&mut chunk)
+                .map_err(|e| format!("Failed to read from Suricata command socket: {}", e))?;
+            if n == 0 {
+                return Err("Suricata closed the command socket".to_string());
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
+
+fn reload_blocking(socket: &Path) -> Result<ReloadResult, String> {
+    let mut client = CommandClient::connect(socket)?;
+    client.command("reload-rules")?;
+
+    // One entry per detection engine
+    let stats = client.command("ruleset-stats")?;
+    let engines = stats.as_array().cloned().unwrap_or_default();
+    let sum = |key: &str| {
+        engines
+            .iter()
+            .map(|engine| engine.get(key).and_then(|v| v.as_u64()))
+            .sum::<Option<u64>>()
+    };
+    let rules_loaded = sum("rules_loaded");
+    let rules_failed = sum("rules_failed");
+
+    let message = match (rules_loaded, rules_failed) {
+        (Some(loaded), Some(failed)) if failed > 0 => format!(
+            "Suricata reloaded {} rules, {} failed to load",
+            loaded, failed
+        ),
+        (Some(loaded), _) => format!("Suricata reloaded {} rules", loaded),
+        _ => "Suricata reloaded the rules".to_string(),
+    };
+
+    Ok(ReloadResult {
+        reloaded: true,
+        rules_loaded,
+        rules_failed,
+        message,
+    })
+}
+
+// Ask a running Suricata to reload its rules. Returns None when no Suricata
+// started by the app is listening on the command socket.
+pub async fn reload(app: &AppHandle) -> Result<Option<ReloadResult>, String> {
+    let socket = paths::command_socket()?;
+    if !socket.exists() {
+        return Ok(None);
+    }
+
+    let _ = app.emit(
+        "rules-update-progress",
+        serde_json::json!({
+            "type": "info",
+            "message": "Reloading rules in Suricata..."
+        }),
+    );
+
+    let result = tokio::task::spawn_blocking(move || {
+        // A socket nobody is listening on was left behind by a Suricata that
+        // didn't shut down cleanly
+        if UnixStream::connect(&socket).is_err() {
+            let _ = std::fs::remove_file(&socket);
+            return Ok(None);
+        }
+        reload_blocking(&socket).map(Some)
+    })
+    .await
+    .map_err(|e| format!("Rule reload task failed: {}", e))?;
+
+    if let Ok(Some(result)) = &result {
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": result.message
+            }),
+        );
+    }
+    result
+}
+
+// Suricata is running but can't be reached on its command socket, e.g. a
+// build without unix socket support or a socket that failed to bind
+fn not_reachable(app: &AppHandle) -> Option<ReloadResult> {
+    if !crate::suricata_running(app) {
+        return None;
+    }
+    Some(ReloadResult {
+        reloaded: false,
+        rules_loaded: None,
+        rules_failed: None,
+        message:
+            "Suricata can't be reached on its command socket, restart Suricata to apply the rules"
+                .to_string(),
+    })
+}
+
+// Reload after the rules on disk changed. A failed reload is reported in the
+// result instead of failing the change that triggered it. None means
+// Suricata is not running.
+pub async fn reload_changed(app: &AppHandle) -> Option<ReloadResult> {
+    match reload(app).await {
+        Ok(Some(result)) => Some(result),
+        Ok(None) => not_reachable(app),
+        Err(e) => Some(ReloadResult {
+            reloaded: false,
+            rules_loaded: None,
+            rules_failed: None,
+            message: e,
+        }),
+    }
+}
+
+#[tauri::command]
+pub async fn reload_rules(app: AppHandle) -> Result<ReloadResult, String> {
+    match reload(&app).await? {
+        Some(result) => Ok(result),
+        None => not_reachable(&app).ok_or_else(|| "Suricata is not running".to_string()),
+    }
+}
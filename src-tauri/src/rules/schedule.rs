@@ -0,0 +1,255 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::hash::{BuildHasher, Hasher};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::UpdateReport;
+use crate::paths;
+use crate::settings;
+
+// How often the scheduler checks whether an update is due
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// A failed scheduled update is retried after at most this long
+const RETRY_HOURS: i64 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateFrequency {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+    // Every interval_hours
+    Interval,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSchedule {
+    pub frequency: UpdateFrequency,
+    // Only used with the interval frequency
+    pub interval_hours: u64,
+    // Random delay of up to this many minutes added to every scheduled
+    // update, so installs don't all hit the rule servers at once
+    pub jitter_minutes: u64,
+}
+
+impl Default for UpdateSchedule {
+    fn default() -> Self {
+        Self {
+            frequency: UpdateFrequency::Off,
+            interval_hours: 24,
+            jitter_minutes: 30,
+        }
+    }
+}
+
+impl UpdateSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.frequency == UpdateFrequency::Interval && self.interval_hours == 0 {
+            return Err("Rule update interval must be at least one hour".to_string());
+        }
+        Ok(())
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        match self.frequency {
+            UpdateFrequency::Off => None,
+            UpdateFrequency::Daily => Some(Duration::days(1)),
+            UpdateFrequency::Weekly => Some(Duration::weeks(1)),
+            UpdateFrequency::Interval => Some(Duration::hours(self.interval_hours.max(1) as i64)),
+        }
+    }
+
+    fn jitter(&self) -> Duration {
+        if self.jitter_minutes == 0 {
+            return Duration::zero();
+        }
+        // RandomState is seeded randomly, which is all the randomness needed here
+        let random = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        Duration::seconds((random % (self.jitter_minutes * 60)) as i64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateOutcome {
+    pub ok: bool,
+    pub scheduled: bool,
+    pub message: String,
+}
+
+// Persisted state of rule updates, shown in the UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateStatus {
+    // RFC 3339 times of the last update attempt and the last successful one
+    pub last_update: Option<String>,
+    pub last_success: Option<String>,
+    pub last_outcome: Option<UpdateOutcome>,
+    // When the scheduler will run next, None when scheduling is off
+    pub next_update: Option<String>,
+    // Schedule next_update was computed for, so a settings change reschedules
+    pub schedule: Option<UpdateSchedule>,
+    pub running: bool,
+}
+
+// Held by everything that writes suricata.rules: rule updates, local rule
+// and threat intel changes and rollbacks. They share the staging file and
+// would otherwise overwrite each other's results.
+#[derive(Default)]
+pub struct UpdateLock {
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl UpdateLock {
+    // Take the lock without waiting, failing while another change runs
+    pub fn try_acquire(&self) -> Result<tokio::sync::MutexGuard<'_, ()>, String> {
+        self.lock
+            .try_lock()
+            .map_err(|_| "A rule update is already running, try again when it finishes".to_string())
+    }
+}
+
+fn status_path() -> Result<std::path::PathBuf, String> {
+    Ok(paths::rules_dir()?.join("update-status.json"))
+}
+
+fn load_status() -> UpdateStatus {
+    status_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_status(app: &AppHandle, status: &UpdateStatus) -> Result<(), String> {
+    let path = status_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create rules directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(status)
+        .map_err(|e| format!("Failed to serialize update status: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write update status: {}", e))?;
+    let _ = app.emit("rules-update-status", status);
+    Ok(())
+}
+
+fn parse_time(time: &Option<String>) -> Option<DateTime<Utc>> {
+    time.as_ref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+// Work out when the next scheduled update should run
+fn next_update(schedule: &UpdateSchedule, status: &UpdateStatus) -> Option<DateTime<Utc>> {
+    let interval = schedule.interval()?;
+    let now = Utc::now();
+
+    let next = match (&status.last_outcome, parse_time(&status.last_update)) {
+        (Some(outcome), Some(last)) if !outcome.ok => {
+            last + interval.min(Duration::hours(RETRY_HOURS))
+        }
+        (_, Some(last)) => last + interval,
+        // Never updated, run as soon as the jitter allows
+        (_, None) => now,
+    };
+    Some(next.max(now) + schedule.jitter())
+}
+
+// Run a rule update and record its outcome. Fails without updating if
+// another update is already running.
+pub async fn update_and_record(app: AppHandle, scheduled: bool) -> Result<UpdateReport, String> {
+    let update_lock = app.state::<UpdateLock>();
+    let _guard = update_lock.try_acquire()?;
+
+    let mut status = load_status();
+    status.running = true;
+    let _ = save_status(&app, &status);
+
+    let result = super::run_update(app.clone()).await;
+
+    let now = Utc::now().to_rfc3339();
+    status.running = false;
+    status.last_update = Some(now.clone());
+    status.last_outcome = Some(match &result {
+        Ok(_) => {
+            status.last_success = Some(now);
+            UpdateOutcome {
+                ok: true,
+                scheduled,
+                message: "Rules updated".to_string(),
+            }
+        }
+        Err(e) => UpdateOutcome {
+            ok: false,
+            scheduled,
+            message: e.clone(),
+        },
+    });
+
+    // Scheduling restarts from this update, manual or not
+    if let Ok(settings) = settings::load() {
+        status.next_update =
+            next_update(&settings.update_schedule, &status).map(|time| time.to_rfc3339());
+        status.schedule = Some(settings.update_schedule);
+    }
+    // The update itself is done, don't turn it into an error because the
+    // status couldn't be written. The UI still gets the outcome, with the
+    // reason the status wasn't saved.
+    if let Err(e) = save_status(&app, &status) {
+        if let Some(outcome) = &mut status.last_outcome {
+            outcome.message = format!("{} ({})", outcome.message, e);
+        }
+        let _ = app.emit("rules-update-status", &status);
+    }
+
+    result
+}
+
+// Background task started with the app. Checks every minute whether a
+// scheduled update is due, picking up schedule changes from the settings.
+pub async fn run_scheduler(app: AppHandle) {
+    // A previous run may have been interrupted by closing the app
+    let mut status = load_status();
+    if status.running {
+        status.running = false;
+        let _ = save_status(&app, &status);
+    }
+
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+
+        let Ok(settings) = settings::load() else {
+            continue;
+        };
+        let schedule = settings.update_schedule;
+        let mut status = load_status();
+
+        if status.schedule.as_ref() != Some(&schedule) || status.next_update.is_none() {
+            let next = next_update(&schedule, &status).map(|time| time.to_rfc3339());
+            if status.next_update != next || status.schedule.as_ref() != Some(&schedule) {
+                status.next_update = next;
+                status.schedule = Some(schedule.clone());
+                let _ = save_status(&app, &status);
+            }
+        }
+
+        let due = parse_time(&status.next_update).is_some_and(|next| next <= Utc::now());
+        if !due {
+            continue;
+        }
+
+        // Failures are recorded in the update status. The only one that
+        // isn't is another update already running, which records its own.
+        let _ = update_and_record(app.clone(), true).await;
+    }
+}
+
+#[tauri::command]
+pub fn get_update_status() -> UpdateStatus {
+    load_status()
+}
@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::settings;
+
+// Suricata version substituted for %(__version__)s in source URLs
+const SURICATA_VERSION: &str = "7.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleSourceType {
+    TarGz,
+    Zip,
+    // A single plain .rules file
+    Rules,
+}
+
+// A rule source as stored in the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSource {
+    pub name: String,
+    // May contain %(__version__)s and %(secret-code)s placeholders, as used by
+    // suricata-update
+    pub url: String,
+    #[serde(rename = "type")]
+    pub source_type: RuleSourceType,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Extra header sent with the request, e.g. "Authorization: Bearer abc"
+    #[serde(default)]
+    pub auth_header: String,
+    // Substituted for %(secret-code)s, e.g. an ET Pro subscription code
+    #[serde(default)]
+    pub secret_code: String,
+    // Used to pick between rules with the same sid from different sources,
+    // higher wins
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RuleSource {
+    fn new(name: &str, url: &str, source_type: RuleSourceType) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            source_type,
+            enabled: true,
+            auth_header: String::new(),
+            secret_code: String::new(),
+            priority: 0,
+        }
+    }
+
+    // The URL to download, with placeholders filled in
+    pub fn resolved_url(&self) -> Result<String, String> {
+        let url = self.url.replace("%(__version__)s", SURICATA_VERSION);
+        if url.contains("%(secret-code)s") {
+            if self.secret_code.is_empty() {
+                return Err(format!("Rule source {} requires a secret code", self.name));
+            }
+            return Ok(url.replace("%(secret-code)s", &self.secret_code));
+        }
+        Ok(url)
+    }
+
+    // The URL with the secret code masked, safe to show in the UI
+    pub fn display_url(&self) -> String {
+        self.url
+            .replace("%(__version__)s", SURICATA_VERSION)
+            .replace("%(secret-code)s", "***")
+    }
+
+    // Split auth_header into a header name and value
+    pub fn auth_header(&self) -> Result<Option<(String, String)>, String> {
+        if self.auth_header.trim().is_empty() {
+            return Ok(None);
+        }
+        match self.auth_header.split_once(':') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok(Some((name.trim().to_string(), value.trim().to_string())))
+            }
+            _ => Err(format!(
+                "Invalid auth header for rule source {}: expected \"Name: value\"",
+                self.name
+            )),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Rule source name must not be empty".to_string());
+        }
+        // Names end up in file names. Catalog names like et/open are allowed,
+        // but no part may be empty or only dots.
+        let valid = !self.name.contains('\\')
+            && !self.name.chars().any(|c| c.is_control())
+            && self
+                .name
+                .split('/')
+                .all(|part| !part.trim().is_empty() && !part.chars().all(|c| c == '.'));
+        if !valid {
+            return Err(format!("Invalid rule source name {}", self.name));
+        }
+        if self.name == super::local::LOCAL_SOURCE {
+            return Err(format!(
+                "Rule source name {} is reserved for local rules",
+                self.name
+            ));
+        }
+        if self.name == super::ioc::IOC_SOURCE {
+            return Err(format!(
+                "Rule source name {} is reserved for threat intel rules",
+                self.name
+            ));
+        }
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(format!(
+                "Rule source {} must have an http:// or https:// URL",
+                self.name
+            ));
+        }
+        self.auth_header()?;
+        Ok(())
+    }
+}
+
+// Check every configured source. Sources must not share a name, or a file
+// name for their cache and support files, as et/open and et_open would.
+pub fn validate_all(sources: &[RuleSource]) -> Result<(), String> {
+    let mut stems: HashMap<String, &str> = HashMap::new();
+    for source in sources {
+        source.validate()?;
+        if let Some(other) = stems.insert(super::cache::file_stem(&source.name), &source.name) {
+            if other == source.name {
+                return Err(format!(
+                    "Rule source {} is configured more than once",
+                    other
+                ));
+            }
+            return Err(format!(
+                "Rule sources {} and {} would share their downloaded files, rename one",
+                other, source.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+// An entry in the built-in source index, modelled on suricata-update's
+// index.yaml
+struct IndexEntry {
+    name: &'static str,
+    vendor: &'static str,
+    summary: &'static str,
+    url: &'static str,
+    source_type: RuleSourceType,
+    license: &'static str,
+    subscription: bool,
+}
+
+const INDEX: &[IndexEntry] = &[
+    IndexEntry {
+        name: "et/open",
+        vendor: "Proofpoint",
+        summary: "Emerging Threats Open Ruleset",
+        url: "https://rules.emergingthreats.net/open/suricata-%(__version__)s/emerging.rules.tar.gz",
+        source_type: RuleSourceType::TarGz,
+        license: "MIT",
+        subscription: false,
+    },
+    IndexEntry {
+        name: "et/pro",
+        vendor: "Proofpoint",
+        summary: "Emerging Threats Pro Ruleset",
+        url: "https://rules.emergingthreatspro.com/%(secret-code)s/suricata-%(__version__)s/etpro.rules.tar.gz",
+        source_type: RuleSourceType::TarGz,
+        license: "Commercial",
+        subscription: true,
+    },
+    IndexEntry {
+        name: "oisf/trafficid",
+        vendor: "OISF",
+        summary: "Suricata Traffic ID ruleset",
+        url: "https://openinfosecfoundation.org/rules/trafficid/trafficid.rules",
+        source_type: RuleSourceType::Rules,
+        license: "MIT",
+        subscription: false,
+    },
+    IndexEntry {
+        name: "pawpatrules",
+        vendor: "pawpatrules",
+        summary: "PAW Patrules ruleset",
+        url: "https://rules.pawpatrules.fr/suricata/paw-patrules.tar.gz",
+        source_type: RuleSourceType::TarGz,
+        license: "CC-BY-SA-4.0",
+        subscription: false,
+    },
+    IndexEntry {
+        name: "sslbl/ja3-fingerprints",
+        vendor: "Abuse.ch",
+        summary: "Abuse.ch Suricata JA3 Fingerprint Ruleset",
+        url: "https://sslbl.abuse.ch/blacklist/ja3_fingerprints.rules",
+        source_type: RuleSourceType::Rules,
+        license: "Non-Commercial",
+        subscription: false,
+    },
+    IndexEntry {
+        name: "tgreen/hunting",
+        vendor: "tgreen",
+        summary: "Threat hunting rules",
+        url: "https://raw.githubusercontent.com/travisbgreen/hunting-rules/master/hunting.rules",
+        source_type: RuleSourceType::Rules,
+        license: "GPLv3",
+        subscription: false,
+    },
+];
+
+// The sources enabled on a fresh install
+pub fn default_sources() -> Vec<RuleSource> {
+    ["et/open", "oisf/trafficid", "pawpatrules"]
+        .iter()
+        .filter_map(|name| INDEX.iter().find(|entry| entry.name == *name))
+        .map(|entry| RuleSource::new(entry.name, entry.url, entry.source_type))
+        .collect()
+}
+
+// A row in the source listing shown to the user: every index entry plus any
+// custom sources, with their configured state
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSourceListing {
+    pub name: String,
+    pub vendor: String,
+    pub summary: String,
+    pub url: String,
+    #[serde(rename = "type")]
+    pub source_type: RuleSourceType,
+    pub license: String,
+    pub subscription: bool,
+    // True if the source is in the settings, enabled or not
+    pub configured: bool,
+    pub enabled: bool,
+    pub custom: bool,
+}
+
+#[tauri::command]
+pub fn list_rule_sources() -> Result<Vec<RuleSourceListing>, String> {
+    let settings = settings::load()?;
+    let mut listing: Vec<RuleSourceListing> = INDEX
+        .iter()
+        .map(|entry| {
+            let configured = settings
+                .rule_sources
+                .iter()
+                .find(|source| source.name == entry.name);
+            RuleSourceListing {
+                name: entry.name.to_string(),
+                vendor: entry.vendor.to_string(),
+                summary: entry.summary.to_string(),
+                url: entry.url.to_string(),
+                source_type: entry.source_type,
+                license: entry.license.to_string(),
+                subscription: entry.subscription,
+                configured: configured.is_some(),
+                enabled: configured.map(|source| source.enabled).unwrap_or(false),
+                custom: false,
+            }
+        })
+        .collect();
+
+    for source in &settings.rule_sources {
+        if INDEX.iter().any(|entry| entry.name == source.name) {
+            continue;
+        }
+        listing.push(RuleSourceListing {
+            name: source.name.clone(),
+            vendor: String::new(),
+            summary: String::new(),
+            url: source.display_url(),
+            source_type: source.source_type,
+            license: String::new(),
+            subscription: !source.secret_code.is_empty() || !source.auth_header.is_empty(),
+            configured: true,
+            enabled: source.enabled,
+            custom: true,
+        });
+    }
+
+    Ok(listing)
+}
+
+// Enable a source. Index sources are added to the settings on first use.
+#[tauri::command]
+pub fn enable_rule_source(name: String, secret_code: Option<String>) -> Result<String, String> {
+    let mut settings = settings::load()?;
+    if let Some(source) = settings
+        .rule_sources
+        .iter_mut()
+        .find(|source| source.name == name)
+    {
+        source.enabled = true;
+        if let Some(secret_code) = secret_code {
+            source.secret_code = secret_code;
+        }
+    } else {
+        let entry = INDEX
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| format!("Unknown rule source: {}", name))?;
+        let mut source = RuleSource::new(entry.name, entry.url, entry.source_type);
+        source.secret_code = secret_code.unwrap_or_default();
+        if entry.subscription && source.secret_code.is_empty() {
+            return Err(format!("Rule source {} requires a secret code", name));
+        }
+        settings.rule_sources.push(source);
+    }
+    validate_all(&settings.rule_sources)?;
+    settings::save(&settings)?;
+    Ok(format!("Enabled rule source {}", name))
+}
+
+#[tauri::command]
+pub fn disable_rule_source(name: String) -> Result<String, String> {
+    let mut settings = settings::load()?;
+    let source = settings
+        .rule_sources
+        .iter_mut()
+        .find(|source| source.name == name)
+        .ok_or_else(|| format!("Rule source {} is not configured", name))?;
+    source.enabled = false;
+    settings::save(&settings)?;
+    Ok(format!("Disabled rule source {}", name))
+}
+
+// Add a custom source, or replace an existing source of the same name
+#[tauri::command]
+pub fn add_rule_source(source: RuleSource) -> Result<String, String> {
+    let mut settings = settings::load()?;
+    let name = source.name.clone();
+    if let Some(existing) = settings
+        .rule_sources
+        .iter_mut()
+        .find(|existing| existing.name == source.name)
+    {
+        *existing = source;
+    } else {
+        settings.rule_sources.push(source);
+    }
+    validate_all(&settings.rule_sources)?;
+    settings::save(&settings)?;
+    Ok(format!("Saved rule source {}", name))
+}
+
+#[tauri::command]
+pub fn remove_rule_source(name: String) -> Result<String, String> {
+    let mut settings = settings::load()?;
+    let count = settings.rule_sources.len();
+    settings.rule_sources.retain(|source| source.name != name);
+    if settings.rule_sources.len() == count {
+        return Err(format!("Rule source {} is not configured", name));
+    }
+    settings::save(&settings)?;
+    Ok(format!("Removed rule source {}", name))
+}
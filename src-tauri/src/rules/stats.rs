@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use super::index::RuleIndexState;
+use super::parser::Rule;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RuleCount {
+    pub total: usize,
+    pub enabled: usize,
+}
+
+impl RuleCount {
+    fn add(&mut self, rule: &Rule) {
+        self.total += 1;
+        if rule.enabled {
+            self.enabled += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RulesetStats {
+    pub total: usize,
+    pub enabled: usize,
+    pub disabled: usize,
+    pub by_source: BTreeMap<String, RuleCount>,
+    pub by_classtype: BTreeMap<String, RuleCount>,
+    pub by_action: BTreeMap<String, RuleCount>,
+    pub by_protocol: BTreeMap<String, RuleCount>,
+    pub by_category: BTreeMap<String, RuleCount>,
+}
+
+const UNKNOWN: &str = "unknown";
+
+// Category of a rule, taken from a category metadata entry, the rule file
+// name ("emerging-scan.rules" is "scan") or the ET message prefix
+// ("ET SCAN ..." is "scan")
+fn category(rule: &Rule) -> String {
+    if let Some(category) = rule.metadata_values("category").next() {
+        return category.to_lowercase();
+    }
+    if let Some(file) = &rule.file {
+        let stem = file.strip_suffix(".rules").unwrap_or(file);
+        let stem = stem.strip_prefix("emerging-").unwrap_or(stem);
+        if !stem.is_empty() {
+            return stem.to_lowercase();
+        }
+    }
+    let mut words = rule.msg.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("ET") | Some("ETPRO"), Some(category)) => category.to_lowercase(),
+        _ => UNKNOWN.to_string(),
+    }
+}
+
+pub fn ruleset_stats(rules: &[Rule]) -> RulesetStats {
+    let mut stats = RulesetStats::default();
+    for rule in rules {
+        stats.total += 1;
+        if rule.enabled {
+            stats.enabled += 1;
+        } else {
+            stats.disabled += 1;
+        }
+
+        let source = rule.source.clone().unwrap_or_else(|| UNKNOWN.to_string());
+        let classtype = rule
+            .classtype
+            .clone()
+            .unwrap_or_else(|| UNKNOWN.to_string());
+        stats.by_source.entry(source).or_default().add(rule);
+        stats.by_classtype.entry(classtype).or_default().add(rule);
+        stats
+            .by_action
+            .entry(rule.action.clone())
+            .or_default()
+            .add(rule);
+        stats
+            .by_protocol
+            .entry(rule.protocol.clone())
+            .or_default()
+            .add(rule);
+        stats
+            .by_category
+            .entry(category(rule))
+            .or_default()
+            .add(rule);
+    }
+    stats
+}
+
+// Summary of the rules in the current suricata.rules
+#[tauri::command]
+pub async fn get_ruleset_stats(
+    rule_index: State<'_, RuleIndexState>,
+) -> Result<RulesetStats, String> {
+    let index = rule_index.get()?;
+    Ok(ruleset_stats(index.rules()))
+}
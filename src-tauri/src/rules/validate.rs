@@ -0,0 +1,230 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+use tokio::process::Command as TokioCommand;
+
+use super::parser::Rule;
+use crate::paths;
+use crate::settings::RuleValidationSettings;
+
+// Test runs before giving up when failing rules are being disabled
+const MAX_ATTEMPTS: u32 = 3;
+
+// Only this many lines of Suricata error output are kept in the report
+const MAX_ERRORS: usize = 50;
+
+// A rule Suricata failed to load
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedRule {
+    // 1-based line in the tested rules file
+    pub line: usize,
+    pub gid: Option<u64>,
+    pub sid: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    // True when the rules were not tested, because validation is turned off
+    // or Suricata is not installed
+    pub skipped: bool,
+    pub attempts: u32,
+    // Rules that were disabled so the ruleset would load
+    pub disabled: Vec<FailedRule>,
+    // Error output from the last failing test run
+    pub errors: Vec<String>,
+}
+
+struct TestOutcome {
+    ok: bool,
+    errors: Vec<String>,
+    failed_lines: Vec<usize>,
+}
+
+// Run suricata -T against a rules file with the installed configuration
+async fn run_test(rules_path: &Path) -> Result<TestOutcome, String> {
+    let log_dir = paths::log_dir()?.join("rule-test");
+    std::fs::create_dir_all(&log_dir)
+        .map_err(|e| format!("Failed to create test log directory: {}", e))?;
+
+    let mut cmd = TokioCommand::new(paths::suricata_exe());
+    cmd.current_dir(paths::SURICATA_DIR)
+        .arg("-T")
+        .arg("-c")
+        .arg(crate::config::active_path()?)
+        .arg("-S")
+        .arg(rules_path)
+        .arg("-l")
+        .arg(&log_dir);
+    let datasets_include = paths::datasets_include()?;
+    if datasets_include.exists() {
+        cmd.arg("--include").arg(datasets_include);
+    }
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run Suricata test: {}", e))?;
+
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(parse_output(output.status.success(), &text))
+}
+
+// Pick the error lines and the line numbers of failing rules out of the test
+// output. Suricata reports them as:
+//   error parsing signature "alert ..." from file C:\...\suricata.rules at line 42
+fn parse_output(ok: bool, text: &str) -> TestOutcome {
+    let line_re = Regex::new(r#"from file .* at line (\d+)"#).unwrap();
+    let mut errors = Vec::new();
+    let mut failed_lines = Vec::new();
+
+    for line in text.lines() {
+        let line = crate::strip_ansi_codes(line.trim());
+        if !line.to_lowercase().contains("error") {
+            continue;
+        }
+        if let Some(number) = line_re
+            .captures(&line)
+            .and_then(|c| c[1].parse::<usize>().ok())
+        {
+            if !failed_lines.contains(&number) {
+                failed_lines.push(number);
+            }
+        }
+        if errors.len() < MAX_ERRORS {
+            errors.push(line);
+        }
+    }
+
+    TestOutcome {
+        ok,
+        errors,
+        failed_lines,
+    }
+}
+
+// Comment out the given 1-based lines, returning the rules that were disabled
+fn disable_lines(text: &str, lines: &[usize]) -> (String, Vec<FailedRule>) {
+    let lines: HashSet<usize> = lines.iter().copied().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut disabled = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        if lines.contains(&number) && !line.trim_start().starts_with('#') {
+            let rule = Rule::parse(line).ok();
+            disabled.push(FailedRule {
+                line: number,
+                gid: rule.as_ref().map(|r| r.gid),
+                sid: rule.as_ref().map(|r| r.sid),
+            });
+            out.push_str("# ");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    (out, disabled)
+}
+
+// Write the rules to a staging file and test them with Suricata. Returns the
+// rules that passed, which differ from the input when failing rules were
+// disabled. The staging file is left for the caller to promote on success and
+// removed on failure.
+pub async fn validate(
+    app: &AppHandle,
+    staging_path: &Path,
+    rules: String,
+    settings: &RuleValidationSettings,
+) -> Result<(String, ValidationReport), String> {
+    let mut report = ValidationReport::default();
+    let mut rules = rules;
+
+    std::fs::write(staging_path, &rules)
+        .map_err(|e| format!("Failed to write staging rules: {}", e))?;
+
+    if !settings.enabled || !paths::suricata_exe().exists() {
+        report.skipped = true;
+        return Ok((rules, report));
+    }
+
+    loop {
+        report.attempts += 1;
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": "Testing rules with Suricata..."
+            }),
+        );
+
+        let outcome = match run_test(staging_path).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                let _ = std::fs::remove_file(staging_path);
+                return Err(e);
+            }
+        };
+        if outcome.ok {
+            report.errors.clear();
+            return Ok((rules, report));
+        }
+        report.errors = outcome.errors;
+
+        if !settings.auto_disable
+            || outcome.failed_lines.is_empty()
+            || report.attempts >= MAX_ATTEMPTS
+        {
+            break;
+        }
+
+        let (updated, disabled) = disable_lines(&rules, &outcome.failed_lines);
+        if disabled.is_empty() {
+            break;
+        }
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": format!(
+                    "Disabled {} rules that failed to load: {}",
+                    disabled.len(),
+                    disabled
+                        .iter()
+                        .map(|r| r.sid.map_or(format!("line {}", r.line), |sid| sid.to_string()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }),
+        );
+        report.disabled.extend(disabled);
+        rules = updated;
+        std::fs::write(staging_path, &rules)
+            .map_err(|e| format!("Failed to write staging rules: {}", e))?;
+    }
+
+    let _ = std::fs::remove_file(staging_path);
+    let mut message =
+        "Suricata failed to load the updated rules, keeping the current rules".to_string();
+    if let Some(first) = report.errors.first() {
+        message.push_str(&format!(": {}", first));
+    }
+    for error in &report.errors {
+        let _ = app.emit(
+            "rules-update-progress",
+            serde_json::json!({
+                "type": "info",
+                "message": error
+            }),
+        );
+    }
+    Err(message)
+}
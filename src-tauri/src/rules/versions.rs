@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::cache::sha256_hex;
+use super::index::RuleIndexState;
+use super::schedule::UpdateLock;
+use super::{parser, SourceReport};
+use crate::paths;
+
+// Number of ruleset snapshots kept, oldest are removed first
+const MAX_VERSIONS: usize = 10;
+
+const METADATA_FILE: &str = "version.json";
+const RULES_FILE: &str = "suricata.rules";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionSource {
+    pub name: String,
+    pub url: String,
+    pub sha256: Option<String>,
+}
+
+// A snapshot of suricata.rules taken after a successful update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleVersion {
+    pub version: String,
+    // RFC 3339
+    pub created_at: String,
+    pub sha256: String,
+    pub rule_count: usize,
+    pub sources: Vec<VersionSource>,
+    // Whether this snapshot matches the current suricata.rules. Not stored.
+    #[serde(default, skip_deserializing)]
+    pub current: bool,
+}
+
+fn versions_dir(rules_dir: &Path) -> PathBuf {
+    rules_dir.join("versions")
+}
+
+// Store the rules written by an update as a new version
+pub fn snapshot(
+    rules_dir: &Path,
+    rules: &str,
+    sources: &[SourceReport],
+) -> Result<RuleVersion, String> {
+    let dir = versions_dir(rules_dir);
+    let now = chrono::Utc::now();
+
+    // Version names sort chronologically. A suffix keeps two updates in the
+    // same second apart.
+    let base = now.format("%Y%m%d-%H%M%S").to_string();
+    let mut version = base.clone();
+    let mut suffix = 1;
+    while dir.join(&version).exists() {
+        suffix += 1;
+        version = format!("{}-{}", base, suffix);
+    }
+
+    let version_dir = dir.join(&version);
+    std::fs::create_dir_all(&version_dir)
+        .map_err(|e| format!("Failed to create version directory: {}", e))?;
+
+    let metadata = RuleVersion {
+        version,
+        created_at: now.to_rfc3339(),
+        sha256: sha256_hex(rules.as_bytes()),
+        rule_count: parser::parse_rules(rules, None).len(),
+        sources: sources
+            .iter()
+            .map(|source| VersionSource {
+                name: source.name.clone(),
+                url: source.url.clone(),
+                sha256: source.sha256.clone(),
+            })
+            .collect(),
+        current: true,
+    };
+
+    std::fs::write(version_dir.join(RULES_FILE), rules)
+        .map_err(|e| format!("Failed to write rules snapshot: {}", e))?;
+    let json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize version metadata: {}", e))?;
+    std::fs::write(version_dir.join(METADATA_FILE), json)
+        .map_err(|e| format!("Failed to write version metadata: {}", e))?;
+
+    let versions = load_versions(rules_dir);
+    if versions.len() > MAX_VERSIONS {
+        for old in &versions[MAX_VERSIONS..] {
+            let _ = std::fs::remove_dir_all(dir.join(&old.version));
+        }
+    }
+
+    Ok(metadata)
+}
+
+// All stored versions, newest first
+fn load_versions(rules_dir: &Path) -> Vec<RuleVersion> {
+    let Ok(entries) = std::fs::read_dir(versions_dir(rules_dir)) else {
+        return Vec::new();
+    };
+
+    let mut versions: Vec<RuleVersion> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path().join(METADATA_FILE)).ok()?;
+            serde_json::from_str(&content).ok()
+        })
+        .collect();
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+    versions
+}
+
+#[tauri::command]
+pub fn list_rule_versions() -> Result<Vec<RuleVersion>, String> {
+    let rules_dir = paths::rules_dir()?;
+    let current = std::fs::read(rules_dir.join(RULES_FILE))
+        .map(|data| sha256_hex(&data))
+        .ok();
+
+    let mut versions = load_versions(&rules_dir);
+    for version in &mut versions {
+        version.current = current.as_deref() == Some(version.sha256.as_str());
+    }
+    Ok(versions)
+}
+
+// Restore suricata.rules from a stored version. With reload set, a running
+// Suricata is made to load the restored rules.
+#[tauri::command]
+pub async fn rollback_rules(
+    app: AppHandle,
+    rule_index: State<'_, RuleIndexState>,
+    version: String,
+    reload: Option<bool>,
+) -> Result<String, String> {
+    let update_lock = app.state::<UpdateLock>();
+    let _guard = update_lock.try_acquire()?;
+    let rules_dir = paths::rules_dir()?;
+    let metadata = load_versions(&rules_dir)
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("Rule version {} not found", version))?;
+
+    let snapshot_path = versions_dir(&rules_dir).join(&version).join(RULES_FILE);
+    let rules = std::fs::read(&snapshot_path)
+        .map_err(|e| format!("Failed to read rules snapshot: {}", e))?;
+    if sha256_hex(&rules) != metadata.sha256 {
+        return Err(format!(
+            "Rule version {} is corrupt, checksum mismatch",
+            version
+        ));
+    }
+
+    std::fs::write(rules_dir.join(RULES_FILE), &rules)
+        .map_err(|e| format!("Failed to write suricata.rules: {}", e))?;
+    rule_index.invalidate();
+
+    let mut message = format!(
+        "Rolled back to rule version {} ({} rules)",
+        version, metadata.rule_count
+    );
+    if reload.unwrap_or(false) {
+        // Prefer a live reload, restarting only a Suricata that can't be
+        // reached on its command socket
+        if let Some(result) = super::reload::reload(&app).await? {
+            message.push_str(&format!(", {}", result.message));
+        } else if crate::restart_suricata(&app).await? {
+            message.push_str(", Suricata restarted");
+        } else {
+            message.push_str(", Suricata is not running");
+        }
+    }
+
+    let _ = app.emit(
+        "rules-update-progress",
+        serde_json::json!({
+            "type": "complete",
+            "message": message
+        }),
+    );
+
+    Ok(message)
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::config::overrides;
+use crate::config::SuricataConfigSettings;
+use crate::http::HttpClient;
+use crate::paths;
+use crate::rules::dedup::DuplicateStrategy;
+use crate::rules::local::SidRange;
+use crate::rules::schedule::UpdateSchedule;
+use crate::rules::sources::{self, RuleSource};
+
+// Application settings, persisted as JSON in the data directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub proxy: ProxySettings,
+    // Paths to additional PEM or DER encoded CA certificates to trust, for
+    // networks doing TLS inspection
+    pub ca_certificates: Vec<String>,
+    pub rule_sources: Vec<RuleSource>,
+    // How to resolve the same sid being provided by more than one source
+    pub duplicate_strategy: DuplicateStrategy,
+    pub rule_validation: RuleValidationSettings,
+    // SIDs handed out to local rules added without one
+    pub local_sid_range: SidRange,
+    // When rules are updated automatically
+    pub update_schedule: UpdateSchedule,
+    // Settings the generated suricata.yaml is built from
+    pub suricata_config: SuricataConfigSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            proxy: ProxySettings::default(),
+            ca_certificates: Vec::new(),
+            rule_sources: sources::default_sources(),
+            duplicate_strategy: DuplicateStrategy::default(),
+            rule_validation: RuleValidationSettings::default(),
+            local_sid_range: SidRange::default(),
+            update_schedule: UpdateSchedule::default(),
+            suricata_config: SuricataConfigSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    // Proxy URL, e.g. http://proxy.example.com:8080. Empty disables the proxy.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    // Hosts, domains or CIDRs that bypass the proxy
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleValidationSettings {
+    // Test updated rules with suricata -T before activating them
+    pub enabled: bool,
+    // Disable rules Suricata fails to load and test again, instead of
+    // rejecting the update
+    pub auto_disable: bool,
+}
+
+impl Default for RuleValidationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auto_disable: false,
+        }
+    }
+}
+
+// Load settings from disk, falling back to defaults if none have been saved
+pub fn load() -> Result<Settings, String> {
+    let path = paths::settings_path()?;
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+pub fn save(settings: &Settings) -> Result<(), String> {
+    let path = paths::settings_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// Check settings before they are saved
+pub fn validate(settings: &Settings) -> Result<(), String> {
+    sources::validate_all(&settings.rule_sources)?;
+    settings.local_sid_range.validate()?;
+    settings.update_schedule.validate()?;
+    settings.suricata_config.validate()
+}
+
+#[tauri::command]
+pub fn get_settings() -> Result<Settings, String> {
+    load()
+}
+
+#[tauri::command]
+pub fn save_settings(
+    http_client: State<'_, HttpClient>,
+    settings: Settings,
+) -> Result<String, String> {
+    // Build the client first so invalid proxy or CA settings are rejected
+    // before they are written to disk
+    let client = crate::http::build_client(&settings)?;
+    validate(&settings)?;
+    // Fail before saving if the config can't be built from the new settings
+    crate::config::build(&settings.suricata_config, &overrides::load()?)?;
+    save(&settings)?;
+    http_client.replace(client);
+    crate::config::generate(&settings.suricata_config)?;
+    Ok("Settings saved".to_string())
+}
@@ -0,0 +1,346 @@
+use serde::Serialize;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+use std::path::Path;
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use crate::paths;
+
+// Port EveBox listens on
+#[cfg(target_os = "windows")]
+const EVEBOX_PORT: u16 = 5636;
+
+// Warn when the log directory has less free space than this
+#[cfg(target_os = "windows")]
+const MIN_FREE_LOG_SPACE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub ok: bool,
+    pub present: bool,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub detail: String,
+    // What the user can do about it, only set when the check failed
+    pub remediation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemCheckReport {
+    // True when every check passed
+    pub ready: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+#[cfg(target_os = "windows")]
+impl CheckResult {
+    fn new(name: &str, ok: bool, detail: String, remediation: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ok,
+            present: ok,
+            version: None,
+            path: None,
+            detail,
+            remediation: if ok {
+                None
+            } else {
+                Some(remediation.to_string())
+            },
+        }
+    }
+
+    fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.display().to_string());
+        self
+    }
+
+    fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+// Run a PowerShell snippet and return its trimmed stdout if it succeeded
+#[cfg(target_os = "windows")]
+fn powershell(script: &str) -> Option<String> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-ExecutionPolicy",
+            "Bypass",
+            "-Command",
+            script,
+        ])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if stdout.is_empty() {
+        None
+    } else {
+        Some(stdout)
+    }
+}
+
+// Run an executable and return the first line of its output
+#[cfg(target_os = "windows")]
+fn first_output_line(exe: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new(exe)
+        .args(args)
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn check_npcap() -> CheckResult {
+    let driver = Path::new(paths::NPCAP_DRIVER);
+    let present = driver.exists();
+    let version = if present {
+        powershell(&format!(
+            "(Get-Item '{}').VersionInfo.ProductVersion",
+            paths::NPCAP_DRIVER
+        ))
+    } else {
+        None
+    };
+    let detail = if present {
+        "Npcap driver is installed".to_string()
+    } else {
+        "Npcap driver not found".to_string()
+    };
+    CheckResult::new(
+        "npcap",
+        present,
+        detail,
+        "Install Npcap from the setup wizard or from https://npcap.com",
+    )
+    .with_path(driver)
+    .with_version(version)
+}
+
+#[cfg(target_os = "windows")]
+fn check_suricata() -> CheckResult {
+    let exe = paths::suricata_exe();
+    let present = exe.exists();
+    // "This is Suricata version 7.0.10 RELEASE"
+    let version = if present {
+        first_output_line(&exe, &["-V"]).map(|line| {
+            line.rsplit("version")
+                .next()
+                .unwrap_or(&line)
+                .trim()
+                .to_string()
+        })
+    } else {
+        None
+    };
+    let detail = if present {
+        "Suricata is installed".to_string()
+    } else {
+        format!("suricata.exe not found in {}", paths::SURICATA_DIR)
+    };
+    CheckResult::new(
+        "suricata",
+        present,
+        detail,
+        "Install Suricata from the setup wizard",
+    )
+    .with_path(&exe)
+    .with_version(version)
+}
+
+#[cfg(target_os = "windows")]
+fn check_evebox() -> CheckResult {
+    let exe = match paths::evebox_exe() {
+        Ok(exe) => exe,
+        Err(e) => return CheckResult::new("evebox", false, e, "Set the USERPROFILE variable"),
+    };
+    let present = exe.exists();
+    let version = if present {
+        first_output_line(&exe, &["version"])
+    } else {
+        None
+    };
+    let detail = if present {
+        "EveBox is installed".to_string()
+    } else {
+        "evebox.exe not found".to_string()
+    };
+    CheckResult::new(
+        "evebox",
+        present,
+        detail,
+        "Install EveBox from the setup wizard",
+    )
+    .with_path(&exe)
+    .with_version(version)
+}
+
+// Capturing with Npcap requires Administrator rights when Npcap was installed
+// with the "Restrict Npcap driver's access to Administrators only" option
+#[cfg(target_os = "windows")]
+fn check_capture_privileges() -> CheckResult {
+    let admin_only = powershell(
+        "(Get-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Services\\npcap\\Parameters' -Name AdminOnly -ErrorAction SilentlyContinue).AdminOnly",
+    )
+    .map(|value| value == "1")
+    .unwrap_or(false);
+    let elevated = powershell(
+        "([Security.Principal.WindowsPrincipal][Security.Principal.WindowsIdentity]::GetCurrent()).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)",
+    )
+    .map(|value| value == "True")
+    .unwrap_or(false);
+
+    let ok = !admin_only || elevated;
+    let detail = match (admin_only, elevated) {
+        (false, _) => "Npcap allows capture by non-administrators".to_string(),
+        (true, true) => "Npcap is restricted to administrators, running elevated".to_string(),
+        (true, false) => {
+            "Npcap is restricted to administrators and the app is not elevated".to_string()
+        }
+    };
+    CheckResult::new(
+        "capture_privileges",
+        ok,
+        detail,
+        "Run Meerkat Desktop as Administrator, or reinstall Npcap without the administrator-only restriction",
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn check_rules() -> CheckResult {
+    let rules_path = match paths::rules_dir() {
+        Ok(dir) => dir.join("suricata.rules"),
+        Err(e) => return CheckResult::new("rules", false, e, "Set the USERPROFILE variable"),
+    };
+    let present = rules_path.exists();
+    let rule_count = std::fs::read_to_string(&rules_path)
+        .map(|content| {
+            content
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .count()
+        })
+        .unwrap_or(0);
+    let ok = rule_count > 0;
+    let detail = if !present {
+        "suricata.rules has not been downloaded".to_string()
+    } else if ok {
+        format!("{} rules loaded", rule_count)
+    } else {
+        "suricata.rules contains no rules".to_string()
+    };
+    let mut result =
+        CheckResult::new("rules", ok, detail, "Run a rule update").with_path(&rules_path);
+    result.present = present;
+    result
+}
+
+#[cfg(target_os = "windows")]
+fn check_log_disk_space() -> CheckResult {
+    let log_dir = match paths::log_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            return CheckResult::new("log_disk_space", false, e, "Set the USERPROFILE variable")
+        }
+    };
+    // The drive letter of the log directory, e.g. "C"
+    let drive = log_dir
+        .to_string_lossy()
+        .chars()
+        .next()
+        .filter(|c| c.is_ascii_alphabetic())
+        .unwrap_or('C');
+    let free = powershell(&format!("(Get-PSDrive -Name '{}').Free", drive))
+        .and_then(|value| value.parse::<u64>().ok());
+
+    let mut result = match free {
+        Some(free) => CheckResult::new(
+            "log_disk_space",
+            free >= MIN_FREE_LOG_SPACE,
+            format!("{} MB free on drive {}:", free / (1024 * 1024), drive),
+            "Free up disk space; Suricata stops logging when the disk is full",
+        ),
+        None => CheckResult::new(
+            "log_disk_space",
+            false,
+            format!("Could not determine free space on drive {}:", drive),
+            "Check that the log directory is on a local drive",
+        ),
+    }
+    .with_path(&log_dir);
+    result.present = log_dir.exists();
+    result
+}
+
+#[cfg(target_os = "windows")]
+fn check_evebox_port() -> CheckResult {
+    let free = std::net::TcpListener::bind(("127.0.0.1", EVEBOX_PORT)).is_ok();
+    // The port being taken by our own EveBox is fine
+    let evebox_running = !free
+        && powershell("(Get-Process evebox -ErrorAction SilentlyContinue) -ne $null")
+            .map(|value| value == "True")
+            .unwrap_or(false);
+    let detail = if free {
+        format!("Port {} is free", EVEBOX_PORT)
+    } else if evebox_running {
+        format!("Port {} is in use by EveBox", EVEBOX_PORT)
+    } else {
+        format!("Port {} is in use by another application", EVEBOX_PORT)
+    };
+    let mut result = CheckResult::new(
+        "evebox_port",
+        free || evebox_running,
+        detail,
+        "Stop the application using the port, e.g. find it with: netstat -ano | findstr 5636",
+    );
+    result.present = true;
+    result
+}
+
+#[tauri::command]
+pub async fn system_check() -> Result<SystemCheckReport, String> {
+    #[cfg(target_os = "windows")]
+    {
+        // The checks shell out to PowerShell, so keep them off the async runtime
+        tokio::task::spawn_blocking(|| {
+            let checks = vec![
+                check_npcap(),
+                check_suricata(),
+                check_evebox(),
+                check_capture_privileges(),
+                check_rules(),
+                check_log_disk_space(),
+                check_evebox_port(),
+            ];
+            SystemCheckReport {
+                ready: checks.iter().all(|check| check.ok),
+                checks,
+            }
+        })
+        .await
+        .map_err(|e| format!("System check failed: {}", e))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err("System check is only available on Windows".to_string())
+    }
+}
@@ -0,0 +1,584 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use tauri::AppHandle;
+
+use crate::paths;
+use crate::rules::reload::{self, ReloadResult};
+
+const DEFAULT_CONTENT: &str = "# Threshold config file\n# Add threshold rules here\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Threshold,
+    EventFilter,
+    Suppress,
+    RateFilter,
+}
+
+impl EntryKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            EntryKind::Threshold => "threshold",
+            EntryKind::EventFilter => "event_filter",
+            EntryKind::Suppress => "suppress",
+            EntryKind::RateFilter => "rate_filter",
+        }
+    }
+
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "threshold" => Some(EntryKind::Threshold),
+            "event_filter" => Some(EntryKind::EventFilter),
+            "suppress" => Some(EntryKind::Suppress),
+            "rate_filter" => Some(EntryKind::RateFilter),
+            _ => None,
+        }
+    }
+}
+
+// One threshold.conf entry. Which of the optional fields are used depends on
+// the kind:
+//   threshold/event_filter gen_id, sig_id, type, track, count, seconds
+//   suppress               gen_id, sig_id[, track, ip]
+//   rate_filter            gen_id, sig_id, track, count, seconds, new_action, timeout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdEntry {
+    pub kind: EntryKind,
+    pub gid: u64,
+    pub sid: u64,
+    #[serde(rename = "type")]
+    pub threshold_type: Option<String>,
+    pub track: Option<String>,
+    pub count: Option<u64>,
+    pub seconds: Option<u64>,
+    pub ip: Option<String>,
+    pub new_action: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+impl ThresholdEntry {
+    pub fn suppress(gid: u64, sid: u64, track: &str, ip: &str) -> Self {
+        Self {
+            kind: EntryKind::Suppress,
+            gid,
+            sid,
+            threshold_type: None,
+            track: Some(track.to_string()),
+            count: None,
+            seconds: None,
+            ip: Some(ip.to_string()),
+            new_action: None,
+            timeout: None,
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (keyword, rest) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("Invalid threshold entry: {}", line))?;
+        let kind = EntryKind::from_keyword(keyword)
+            .ok_or_else(|| format!("Unknown threshold entry type: {}", keyword))?;
+
+        let mut entry = Self {
+            kind,
+            gid: 0,
+            sid: 0,
+            threshold_type: None,
+            track: None,
+            count: None,
+            seconds: None,
+            ip: None,
+            new_action: None,
+            timeout: None,
+        };
+        let mut has_gid = false;
+        let mut has_sid = false;
+
+        for field in split_fields(rest) {
+            let (key, value) = field
+                .split_once(char::is_whitespace)
+                .map(|(key, value)| (key, value.trim()))
+                .ok_or_else(|| format!("Missing value for {}", field))?;
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid {} value: {}", key, value))
+            };
+            match key {
+                "gen_id" => {
+                    entry.gid = number()?;
+                    has_gid = true;
+                }
+                "sig_id" => {
+                    entry.sid = number()?;
+                    has_sid = true;
+                }
+                "type" => entry.threshold_type = Some(value.to_string()),
+                "track" => entry.track = Some(value.to_string()),
+                "count" => entry.count = Some(number()?),
+                "seconds" => entry.seconds = Some(number()?),
+                "ip" => entry.ip = Some(value.to_string()),
+                "new_action" => entry.new_action = Some(value.to_string()),
+                "timeout" => entry.timeout = Some(number()?),
+                _ => return Err(format!("Unknown threshold option: {}", key)),
+            }
+        }
+
+        if !has_gid || !has_sid {
+            return Err("Threshold entries need gen_id and sig_id".to_string());
+        }
+        entry.validate()?;
+        Ok(entry)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let require = |name: &str, present: bool| {
+            if present {
+                Ok(())
+            } else {
+                Err(format!("{} entries need {}", self.kind.keyword(), name))
+            }
+        };
+        let forbid = |name: &str, present: bool| {
+            if present {
+                Err(format!(
+                    "{} entries don't take {}",
+                    self.kind.keyword(),
+                    name
+                ))
+            } else {
+                Ok(())
+            }
+        };
+        let check_track = |allowed: &[&str]| match &self.track {
+            Some(track) if !allowed.contains(&track.as_str()) => Err(format!(
+                "Invalid track {} for {}, expected one of {}",
+                track,
+                self.kind.keyword(),
+                allowed.join(", ")
+            )),
+            _ => Ok(()),
+        };
+
+        match self.kind {
+            EntryKind::Threshold | EntryKind::EventFilter => {
+                require("type", self.threshold_type.is_some())?;
+                require("track", self.track.is_some())?;
+                require("count", self.count.is_some())?;
+                require("seconds", self.seconds.is_some())?;
+                forbid("ip", self.ip.is_some())?;
+                forbid("new_action", self.new_action.is_some())?;
+                forbid("timeout", self.timeout.is_some())?;
+                if let Some(threshold_type) = &self.threshold_type {
+                    if !["threshold", "limit", "both"].contains(&threshold_type.as_str()) {
+                        return Err(format!(
+                            "Invalid type {}, expected threshold, limit or both",
+                            threshold_type
+                        ));
+                    }
+                }
+                if self.kind == EntryKind::Threshold {
+                    check_track(&["by_src", "by_dst", "by_rule", "by_both"])?;
+                } else {
+                    check_track(&["by_src", "by_dst"])?;
+                }
+            }
+            EntryKind::Suppress => {
+                forbid("type", self.threshold_type.is_some())?;
+                forbid("count", self.count.is_some())?;
+                forbid("seconds", self.seconds.is_some())?;
+                forbid("new_action", self.new_action.is_some())?;
+                forbid("timeout", self.timeout.is_some())?;
+                if self.track.is_some() != self.ip.is_some() {
+                    return Err("suppress entries need both track and ip, or neither".to_string());
+                }
+                check_track(&["by_src", "by_dst", "by_either"])?;
+                if let Some(ip) = &self.ip {
+                    validate_ip_spec(ip)?;
+                }
+            }
+            EntryKind::RateFilter => {
+                require("track", self.track.is_some())?;
+                require("count", self.count.is_some())?;
+                require("seconds", self.seconds.is_some())?;
+                require("new_action", self.new_action.is_some())?;
+                require("timeout", self.timeout.is_some())?;
+                forbid("type", self.threshold_type.is_some())?;
+                forbid("ip", self.ip.is_some())?;
+                check_track(&["by_src", "by_dst", "by_rule", "by_both"])?;
+                if let Some(action) = &self.new_action {
+                    if !["alert", "drop", "pass", "reject"].contains(&action.as_str()) {
+                        return Err(format!(
+                            "Invalid new_action {}, expected alert, drop, pass or reject",
+                            action
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ThresholdEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gen_id {}, sig_id {}",
+            self.kind.keyword(),
+            self.gid,
+            self.sid
+        )?;
+        if let Some(threshold_type) = &self.threshold_type {
+            write!(f, ", type {}", threshold_type)?;
+        }
+        if let Some(track) = &self.track {
+            write!(f, ", track {}", track)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ", count {}", count)?;
+        }
+        if let Some(seconds) = self.seconds {
+            write!(f, ", seconds {}", seconds)?;
+        }
+        if let Some(ip) = &self.ip {
+            write!(f, ", ip {}", ip)?;
+        }
+        if let Some(new_action) = &self.new_action {
+            write!(f, ", new_action {}", new_action)?;
+        }
+        if let Some(timeout) = self.timeout {
+            write!(f, ", timeout {}", timeout)?;
+        }
+        Ok(())
+    }
+}
+
+// Split entry options on commas, keeping bracketed ip lists together
+fn split_fields(text: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth <= 0 => {
+                fields.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(text[start..].trim());
+    fields
+        .into_iter()
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+// An address or CIDR, optionally negated
+pub fn validate_address(address: &str) -> Result<(), String> {
+    let address = address.trim();
+    let address = address.strip_prefix('!').unwrap_or(address);
+    let (ip, prefix) = match address.split_once('/') {
+        Some((ip, prefix)) => (ip, Some(prefix)),
+        None => (address, None),
+    };
+    let ip: IpAddr = ip
+        .parse()
+        .map_err(|_| format!("Invalid IP address: {}", address))?;
+    if let Some(prefix) = prefix {
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        match prefix.parse::<u8>() {
+            Ok(prefix) if prefix <= max => {}
+            _ => return Err(format!("Invalid CIDR prefix: {}", address)),
+        }
+    }
+    Ok(())
+}
+
+// The ip option takes an address, a CIDR, an address variable like
+// $HOME_NET, or a bracketed list of those
+pub fn validate_ip_spec(spec: &str) -> Result<(), String> {
+    let spec = spec.trim();
+    if let Some(list) = spec.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let items: Vec<&str> = list.split(',').map(|item| item.trim()).collect();
+        if items.iter().any(|item| item.is_empty()) {
+            return Err(format!("Invalid IP list: {}", spec));
+        }
+        return items.iter().try_for_each(|item| validate_ip_spec(item));
+    }
+    let name = spec.strip_prefix('!').unwrap_or(spec);
+    if let Some(var) = name.strip_prefix('$') {
+        if !var.is_empty() && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Ok(());
+        }
+        return Err(format!("Invalid address variable: {}", spec));
+    }
+    validate_address(spec)
+}
+
+enum Line {
+    // An entry and the line as written, so untouched entries keep their
+    // formatting
+    Entry(ThresholdEntry, String),
+    // Comments, blank lines and lines that failed to parse, kept as is
+    Other(String),
+}
+
+// threshold.conf, parsed line by line so comments and ordering survive a
+// rewrite
+struct ThresholdFile {
+    lines: Vec<Line>,
+}
+
+impl ThresholdFile {
+    fn parse(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    return Line::Other(line.to_string());
+                }
+                match ThresholdEntry::parse(trimmed) {
+                    Ok(entry) => Line::Entry(entry, line.to_string()),
+                    Err(_) => Line::Other(line.to_string()),
+                }
+            })
+            .collect();
+        Self { lines }
+    }
+
+    fn load() -> Result<Self, String> {
+        let path = paths::threshold_path()?;
+        if !path.exists() {
+            return Ok(Self::parse(DEFAULT_CONTENT));
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read threshold.conf: {}", e))?;
+        Ok(Self::parse(&text))
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = paths::threshold_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        }
+        let mut text = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Entry(_, line) | Line::Other(line) => text.push_str(line),
+            }
+            text.push('\n');
+        }
+        std::fs::write(&path, text).map_err(|e| format!("Failed to write threshold.conf: {}", e))
+    }
+
+    fn entry(&self, line: usize) -> Result<&ThresholdEntry, String> {
+        match self.lines.get(line) {
+            Some(Line::Entry(entry, _)) => Ok(entry),
+            _ => Err(format!("No threshold entry on line {}", line + 1)),
+        }
+    }
+
+    fn listing(&self) -> ThresholdListing {
+        let mut listing = ThresholdListing::default();
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                Line::Entry(entry, text) => listing.entries.push(ThresholdListEntry {
+                    line: i,
+                    text: text.trim().to_string(),
+                    entry: entry.clone(),
+                }),
+                Line::Other(text) => {
+                    let trimmed = text.trim();
+                    if trimmed.is_empty() || trimmed.starts_with('#') {
+                        continue;
+                    }
+                    if let Err(error) = ThresholdEntry::parse(trimmed) {
+                        listing.invalid.push(InvalidThresholdLine {
+                            line: i,
+                            text: text.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+        listing
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdListEntry {
+    // 0-based line number, used to edit or remove the entry
+    pub line: usize,
+    pub text: String,
+    pub entry: ThresholdEntry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidThresholdLine {
+    pub line: usize,
+    pub text: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThresholdListing {
+    pub entries: Vec<ThresholdListEntry>,
+    // Lines Suricata would reject, left untouched by edits
+    pub invalid: Vec<InvalidThresholdLine>,
+}
+
+// Check threshold.conf content, failing on the first line Suricata would
+// reject
+pub fn validate_content(text: &str) -> Result<(), String> {
+    match ThresholdFile::parse(text).listing().invalid.first() {
+        Some(invalid) => Err(format!(
+            "threshold.conf line {}: {}",
+            invalid.line + 1,
+            invalid.error
+        )),
+        None => Ok(()),
+    }
+}
+
+// Append an entry to threshold.conf, returning its line
+pub fn add_entry(entry: ThresholdEntry) -> Result<usize, String> {
+    entry.validate()?;
+    let mut file = ThresholdFile::load()?;
+    let text = entry.to_string();
+    file.lines.push(Line::Entry(entry, text));
+    file.save()?;
+    Ok(file.lines.len() - 1)
+}
+
+// Remove the entry on a line
+fn remove_entry(line: usize) -> Result<(), String> {
+    let mut file = ThresholdFile::load()?;
+    file.entry(line)?;
+    file.lines.remove(line);
+    file.save()
+}
+
+// The fields of an eve.json alert needed to suppress it
+#[derive(Debug, Clone, Deserialize)]
+pub struct EveAlert {
+    pub signature_id: u64,
+    pub gid: Option<u64>,
+    pub src_ip: String,
+    pub dest_ip: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressionResult {
+    pub entry: ThresholdEntry,
+    pub text: String,
+    // False when an identical suppression already existed
+    pub added: bool,
+    // Line of the added entry, pass back to undo_suppression with the entry
+    // to remove it again. None when nothing was added, so an existing
+    // suppression can't be undone by mistake.
+    pub line: Option<usize>,
+    pub reload: Option<ReloadResult>,
+}
+
+#[tauri::command]
+pub fn list_thresholds() -> Result<ThresholdListing, String> {
+    Ok(ThresholdFile::load()?.listing())
+}
+
+#[tauri::command]
+pub fn add_threshold(entry: ThresholdEntry) -> Result<ThresholdListing, String> {
+    add_entry(entry)?;
+    list_thresholds()
+}
+
+#[tauri::command]
+pub fn update_threshold(line: usize, entry: ThresholdEntry) -> Result<ThresholdListing, String> {
+    entry.validate()?;
+    let mut file = ThresholdFile::load()?;
+    file.entry(line)?;
+    let text = entry.to_string();
+    file.lines[line] = Line::Entry(entry, text);
+    file.save()?;
+    Ok(file.listing())
+}
+
+#[tauri::command]
+pub fn remove_threshold(line: usize) -> Result<ThresholdListing, String> {
+    remove_entry(line)?;
+    list_thresholds()
+}
+
+// Suppress an alert for its source or destination address, and reload a
+// running Suricata so the suppression takes effect
+#[tauri::command]
+pub async fn suppress_alert(
+    app: AppHandle,
+    alert: EveAlert,
+    track: String,
+) -> Result<SuppressionResult, String> {
+    let ip = match track.as_str() {
+        "by_src" => &alert.src_ip,
+        "by_dst" => &alert.dest_ip,
+        _ => {
+            return Err(format!(
+                "Invalid track {}, expected by_src or by_dst",
+                track
+            ))
+        }
+    };
+    validate_address(ip)?;
+
+    let entry = ThresholdEntry::suppress(alert.gid.unwrap_or(1), alert.signature_id, &track, ip);
+    let exists = ThresholdFile::load()?
+        .lines
+        .iter()
+        .any(|line| matches!(line, Line::Entry(existing, _) if *existing == entry));
+    let line = if exists {
+        None
+    } else {
+        Some(add_entry(entry.clone())?)
+    };
+
+    Ok(SuppressionResult {
+        text: entry.to_string(),
+        entry,
+        added: line.is_some(),
+        line,
+        reload: reload::reload_changed(&app).await,
+    })
+}
+
+// Remove a suppression added by suppress_alert and reload a running Suricata
+#[tauri::command]
+pub async fn undo_suppression(
+    app: AppHandle,
+    line: Option<usize>,
+    entry: ThresholdEntry,
+) -> Result<Option<ReloadResult>, String> {
+    let line = line.ok_or_else(|| {
+        format!(
+            "Suppression {} existed before it was added, remove it from threshold.conf instead",
+            entry
+        )
+    })?;
+    // Only remove the entry if it is still the one that was added
+    let file = ThresholdFile::load()?;
+    if file.entry(line).ok() != Some(&entry) {
+        return Err(format!(
+            "Suppression {} has changed since it was added, remove it from threshold.conf instead",
+            entry
+        ));
+    }
+    remove_entry(line)?;
+    Ok(reload::reload_changed(&app).await)
+}